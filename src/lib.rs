@@ -1,6 +1,32 @@
 pub mod local;
 pub mod sync;
 pub use local::FlagRef;
+pub use local::WeakFlagRef;
 pub use local::FlagCell;
 pub use local::FlagRefOption;
+pub use local::FlagBorrowError;
+pub use local::BorrowConflict;
+pub use local::BorrowKind;
+pub use local::UnwrapError;
+pub use local::StrongLease;
+pub use local::CountLease;
+pub use local::FlagProj;
+pub use local::FlagSnapshot;
+pub use local::ChangeToken;
+pub use local::FlagRefSet;
+pub use local::Identity;
+pub use local::FlagHandle;
+pub use local::FlagHeader;
+pub use local::HEADER_SIZE;
+pub use local::FlagCow;
+pub use local::CowMut;
+pub use local::BorrowedOrStatic;
+pub use local::TrackedRefMut;
+pub use local::PoisonRefMut;
+pub use local::ForceSend;
+pub use local::ForceSync;
+pub use local::retain_live;
+pub use local::with_two_mut;
+pub use local::catch_borrow_conflict;
+pub use local::drain_deferred;
 
@@ -1,10 +1,21 @@
-use std::alloc::{dealloc, Layout};
-use std::cell::{Cell, RefCell, RefMut, Ref};
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::any::Any;
+#[cfg(feature = "debug_borrow_tracking")]
+use std::backtrace::Backtrace;
+use std::cell::{BorrowError, BorrowMutError, Cell, OnceCell, RefCell, RefMut, Ref};
+use std::collections::{hash_map, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 use std::mem;
-use std::mem::ManuallyDrop;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::num::NonZeroUsize;
 use std::ops::{Deref, DerefMut};
-use std::ptr::{drop_in_place, NonNull};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr::{self, drop_in_place, NonNull};
+use std::rc::Rc;
+use std::thread;
 
 macro_rules! dangling_then_return {
     ($ptr:expr , $thing:expr) => {
@@ -23,18 +34,301 @@ pub fn is_dangling<T: ?Sized>(ptr: *const T) -> bool {
     ptr.cast::<()>().addr() == usize::MAX
 }
 
+/// 析构内部数据，并释放核心结构的内存
+///
+/// # SAFETY
+/// 计数已归零即不存在任何其他持有者，内部数据未被取出，且此后再无访问。
+///
+/// 延迟析构模式下不立即析构，而是加入线程局部队列，详见 [`drain_deferred`]
+unsafe fn release_header<T: ?Sized>(ptr: NonNull<FlagHeader<T>>) {
+    // SAFETY: 调用者保证指针有效
+    if unsafe { ptr.as_ref().flags.get() } & FLAG_DEFERRED != 0 {
+        // 胖指针无法直接擦除为 NonNull<u8>，将其装箱后入队
+        let queued = DEFERRED.try_with(|queue| {
+            let boxed = NonNull::from(Box::leak(Box::new(ptr))).cast();
+            queue.0.borrow_mut().push((boxed, release_erased::<T>));
+        });
+        // 线程退出、队列正在或已经销毁时无法入队，退回立即析构
+        if queued.is_ok() {
+            return;
+        }
+    }
+    unsafe { release_header_now(ptr) }
+}
+
+/// 立即析构内部数据，并释放核心结构的内存
+///
+/// # SAFETY
+/// 同 [`release_header`]
+unsafe fn release_header_now<T: ?Sized>(ptr: NonNull<FlagHeader<T>>) {
+    unsafe {
+        // 先手动析构ManuallyDrop包裹的T，再析构外层结构；数据已被取走时跳过
+        if ptr.as_ref().flags.get() & FLAG_DATA_DROPPED == 0 {
+            let refcell = &mut (*ptr.as_ptr()).data;
+            let mut_man_drop = RefCell::get_mut(refcell);
+            ManuallyDrop::drop(mut_man_drop);
+        }
+        
+        release_weak(ptr);
+    }
+}
+
+/// 类型擦除的 [`release_header_now`]，供延迟析构队列使用
+///
+/// # SAFETY
+/// `ptr` 必须来自装箱的 `NonNull<FlagHeader<T>>`，其余同 [`release_header`]
+unsafe fn release_erased<T: ?Sized>(ptr: NonNull<u8>) {
+    unsafe {
+        let boxed = Box::from_raw(ptr.cast::<NonNull<FlagHeader<T>>>().as_ptr());
+        release_header_now(*boxed)
+    }
+}
+
+/// 延迟析构队列中的一项：类型擦除的装箱核心结构指针及其析构函数
+type DeferredEntry = (NonNull<u8>, unsafe fn(NonNull<u8>));
+
+/// 延迟析构队列，线程退出时析构剩余的全部数据
+struct DeferredQueue(RefCell<Vec<DeferredEntry>>);
+
+impl Drop for DeferredQueue {
+    fn drop(&mut self) {
+        drain_queue(self);
+    }
+}
+
+thread_local! {
+    static DEFERRED: DeferredQueue = const { DeferredQueue(RefCell::new(Vec::new())) };
+}
+
+fn drain_queue(queue: &DeferredQueue) -> usize {
+    let mut drained = 0;
+    // 析构过程中可能有新的数据加入队列，循环直到队列为空；
+    // 线程退出时队列本身正在销毁，此时新的数据不会入队，而是在 release_header 中立即析构
+    loop {
+        let batch = mem::take(&mut *queue.0.borrow_mut());
+        if batch.is_empty() {
+            return drained;
+        }
+        drained += batch.len();
+        for (ptr, release) in batch {
+            // SAFETY: 入队时已满足 release_header 的要求，且每项只会出队一次
+            unsafe { release(ptr) };
+        }
+    }
+}
+
+/// 析构当前线程上所有被延迟析构的数据，返回析构的数量
+///
+/// 仅 [`FlagCell::new_deferred`] 创建的数据会被延迟析构。
+/// 未被手动处理的数据会在线程退出时析构。
+pub fn drain_deferred() -> usize {
+    DEFERRED.with(drain_queue)
+}
+
+/// 释放强持有者共同持有的隐式弱计数或一个 [`WeakFlagRef`] 的弱计数，归零时释放核心结构的内存
+///
+/// # SAFETY
+/// 内部数据已被取出或已析构，且调用者持有的这份弱计数此后不再使用。
+unsafe fn release_weak<T: ?Sized>(ptr: NonNull<FlagHeader<T>>) {
+    // SAFETY: 弱计数归零前内存始终有效
+    let weak = unsafe { &ptr.as_ref().weak };
+    let new_weak = weak.get() - 1;
+    weak.set(new_weak);
+    if new_weak == 0 {
+        // SAFETY: 已无任何持有者
+        unsafe { dealloc_header(ptr) }
+    }
+}
+
+/// 释放核心结构的内存，不析构内部数据
+///
+/// # SAFETY
+/// 内部数据已被取出或已析构，且已不存在任何强或弱持有者。
+unsafe fn dealloc_header<T: ?Sized>(ptr: NonNull<FlagHeader<T>>) {
+    unsafe {
+        // 布局须在析构前取得；内部数据已不再使用，动态大小仅由指针元数据决定
+        let layout = Layout::for_value(ptr.as_ref());
+        let custom = ptr.as_ref().extra.get().and_then(|extra| extra.dealloc.get());
+        // 析构剩余结构 + 释放内存
+        drop_in_place(ptr.as_ptr());
+        match custom {
+            Some(custom) => (custom.call)(ptr.cast(), custom.func),
+            None => dealloc(
+                ptr.as_ptr() as *mut u8,
+                layout
+            ),
+        }
+    }
+}
+
+/// 计算内部数据为 `[E]`、长度为 `len` 时核心结构的内存布局
+///
+/// 结果与对应胖指针上的 [`Layout::for_value`] 一致
+///
+/// # Panics
+/// 大小溢出 `isize::MAX` 时 panic
+fn slice_header_layout<E>(len: usize) -> Layout {
+    // 零长数组版本与切片版本共享同一前缀布局，据此得到切片的起始偏移
+    let probe = RefCell::new(ManuallyDrop::new([] as [E; 0]));
+    let in_refcell = probe.as_ptr().addr() - ptr::from_ref(&probe).addr();
+    let offset = mem::offset_of!(FlagHeader<[E; 0]>, data) + in_refcell;
+    let align = mem::align_of::<FlagHeader<[E; 0]>>();
+    Layout::array::<E>(len)
+        .ok()
+        .and_then(|array| offset.checked_add(array.size()))
+        .and_then(|size| Layout::from_size_align(size, align).ok())
+        .expect("FlagCell 切片容量溢出")
+        .pad_to_align()
+}
+
+/// 嵌套模式标记位，详见 [`FlagCell::new_nested`]
+const FLAG_NESTED: u8 = 1 << 0;
+/// 延迟析构模式标记位，详见 [`FlagCell::new_deferred`]
+const FLAG_DEFERRED: u8 = 1 << 1;
+/// 严格模式标记位，详见 [`FlagCell::new_strict`]
+const FLAG_STRICT: u8 = 1 << 2;
+/// 冻结标记位，详见 [`FlagCell::freeze`]
+const FLAG_FROZEN: u8 = 1 << 3;
+/// 中毒模式标记位，详见 [`FlagCell::new_poisoning`]
+const FLAG_POISONING: u8 = 1 << 4;
+/// 已中毒标记位，详见 [`FlagCell::new_poisoning`]
+const FLAG_POISONED: u8 = 1 << 5;
+/// 存在存活的 `FlagCell` 标记位，用于区分“`FlagCell` 已销毁”与“`FlagCell` 存活但已禁用”
+const FLAG_OWNED: u8 = 1 << 6;
+/// 内部数据已被取走或析构标记位，详见 [`FlagCell::force_unwrap`] 与 [`FlagCell::drop_payload`]
+const FLAG_DATA_DROPPED: u8 = 1 << 7;
+
+/// 类型擦除后的自定义释放函数：按原类型调用的入口，以及被擦除的用户函数指针
+#[derive(Debug, Clone, Copy)]
+struct ErasedDealloc {
+    call: unsafe fn(NonNull<u8>, *const ()),
+    func: *const (),
+}
+
+/// [`ErasedDealloc`] 的调用入口，将指针与函数还原为原类型后调用
+///
+/// # SAFETY
+/// `func` 必须由 `fn(NonNull<FlagHeader<T>>)` 擦除而来，`ptr` 指向同一 `T` 的核心结构
+unsafe fn call_dealloc<T>(ptr: NonNull<u8>, func: *const ()) {
+    // SAFETY: 调用者保证 func 的原类型，函数指针与 *const () 大小相同
+    let func = unsafe { mem::transmute::<*const (), fn(NonNull<FlagHeader<T>>)>(func) };
+    func(ptr.cast())
+}
+
+/// 核心结构中不常用的元数据，首次需要时才在堆上分配，不占用每个 `FlagCell` 的固定开销
+#[derive(Debug, Default)]
+struct HeaderExtra {
+    /// 变更版本，详见 [`FlagCell::version`]
+    version: Cell<u64>,
+    /// 强租约数量，详见 [`StrongLease`]
+    leases: Cell<usize>,
+    /// 嵌套禁用深度，仅在嵌套模式下使用，详见 [`FlagCell::new_nested`]
+    disable_depth: Cell<u32>,
+    /// 用户自定义标签，详见 [`FlagCell::tag`]
+    tag: Cell<u32>,
+    /// 计数阈值回调，详见 [`FlagCell::warn_on_ref_count_over`]
+    ref_watch: RefCell<Option<RefCountWatch>>,
+    /// 自定义的内存释放函数，详见 [`FlagCell::from_raw_header`]
+    dealloc: Cell<Option<ErasedDealloc>>,
+}
+
+/// 堆上的核心结构
+///
+/// 采用 `#[repr(C)]` 保证布局稳定：元数据字段在前，偏移与 `T` 无关；数据字段固定在最后，
+/// 这也是内部数据可以为动态大小类型（切片、`dyn Trait`）的前提。
+///
+/// 元数据字段均为内部实现，其编码方式不属于公开接口；外部代码需要定位内部数据时使用 [`DATA_OFFSET`](Self::DATA_OFFSET)。
+#[repr(C)]
+#[derive(Debug)]
+pub struct FlagHeader<T: ?Sized> {
+    /// 带符号的引用计数，正数表示启用，负数表示禁用，绝对值为 FlagCell 与 FlagRef 总数
+    count: Cell<isize>,
+    /// [`WeakFlagRef`] 数量，另加所有强持有者共同持有的一个隐式计数（内部数据析构时释放）
+    weak: Cell<usize>,
+    /// 代数，详见 [`FlagCell::generation`]
+    generation: Cell<u64>,
+    /// 模式标记位
+    flags: Cell<u8>,
+    /// 不常用的元数据，见 [`HeaderExtra`]
+    extra: OnceCell<Box<HeaderExtra>>,
+    /// 最近一次成功借用的调用栈，仅在启用 `debug_borrow_tracking` feature 时存在
+    #[cfg(feature = "debug_borrow_tracking")]
+    last_borrow: RefCell<Option<Backtrace>>,
+    /// 内部数据
+    data: RefCell<ManuallyDrop<T>>,
+}
+
+impl<T> FlagHeader<T> {
+    /// 内部数据字段（`RefCell<ManuallyDrop<T>>`）相对核心结构起始地址的字节偏移
+    pub const DATA_OFFSET: usize = mem::offset_of!(FlagHeader<T>, data);
+    
+    /// 创建一个启用状态、计数为1（即一个 FlagCell）的核心结构
+    ///
+    /// 通常无需直接使用；在自行提供的内存中构造后可交给 [`FlagCell::from_raw_header`]。
+    pub fn new(value: T) -> Self {
+        Self {
+            count: Cell::new(1),
+            weak: Cell::new(1),
+            generation: Cell::new(0),
+            flags: Cell::new(0),
+            extra: OnceCell::new(),
+            #[cfg(feature = "debug_borrow_tracking")]
+            last_borrow: RefCell::new(None),
+            data: RefCell::new(ManuallyDrop::new(value)),
+        }
+    }
+}
+
+/// 计数阈值与越过阈值时的回调，详见 [`FlagCell::warn_on_ref_count_over`]
+struct RefCountWatch {
+    threshold: isize,
+    callback: Box<dyn Fn(isize)>,
+}
+
+impl fmt::Debug for RefCountWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefCountWatch")
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+const _: () = assert!(mem::offset_of!(FlagHeader<u32>, count) == 0);
+const _: () = assert!(
+    mem::offset_of!(FlagHeader<u8>, generation) == mem::offset_of!(FlagHeader<[u64; 4]>, generation)
+);
+
+/// 零大小数据 `FlagHeader<()>` 的大小，即每个 `FlagCell` 的固定开销
+///
+/// 常驻核心结构的只有几乎每次操作都要用到的元数据：计数、弱计数、代数、模式标记位、
+/// 附属元数据指针，以及内部 `RefCell` 的借用标记，共 6 个机器字（64 位平台上为 48 字节）。
+/// 租约、嵌套深度、标签、计数回调与自定义释放函数等不常用的元数据放在首次使用时才分配的附属结构中，
+/// 不使用这些功能的 `FlagCell` 不为其付出任何空间。
+///
+/// 默认 feature 下由编译期断言保证不超过上述预算；启用 `debug_borrow_tracking` 时另加调用栈记录的大小。
+pub const HEADER_SIZE: usize = mem::size_of::<FlagHeader<()>>();
+
+/// [`HEADER_SIZE`] 的预算：6 个机器字
+#[cfg(all(target_pointer_width = "64", not(feature = "debug_borrow_tracking")))]
+const HEADER_BUDGET: usize = 6 * mem::size_of::<usize>();
+
+#[cfg(all(target_pointer_width = "64", not(feature = "debug_borrow_tracking")))]
+const _: () = assert!(HEADER_SIZE <= HEADER_BUDGET);
+// 零大小数据时核心结构的布局大小仍非零，分配与释放无需特殊处理零大小布局
+const _: () = assert!(HEADER_SIZE > 0);
+
 #[repr(transparent)]
 #[derive(Debug)]
-struct InnerFlag<T>(NonNull<(RefCell<ManuallyDrop<T>>, Cell<isize>)>);
+struct InnerFlag<T: ?Sized>(NonNull<FlagHeader<T>>);
 
 // 不可能创建一个空的自己，不作null校验
 // 在内存被 dealloc 后，正常使用情况下应当不存在可能的InnerFlag被持有，当InnerFlag存在时，内存应当始终有效，因此不作任何判悬垂校验
 // TODO：引用计数理论上可以达到 isize::MAX，但应该不太可能有人做得到，暂时不写溢出检查，直接panic
-impl<T> InnerFlag<T> {
+impl<T: ?Sized> InnerFlag<T> {
     /// 从合法指针创建InnerFlag
     #[allow(dead_code)]
     // TODO：允许外部得到数据引用时暴露此方法
-    pub fn from_ptr(ptr: NonNull<(RefCell<ManuallyDrop<T>>, Cell<isize>)>) -> Self {
+    pub fn from_ptr(ptr: NonNull<FlagHeader<T>>) -> Self {
         Self(ptr)
     }
     
@@ -44,7 +338,7 @@ impl<T> InnerFlag<T> {
     #[inline]
     pub fn count_ref(&self) -> &Cell<isize> {
         // SAFETY: 仅当指针非空时调用，外部已做is_empty校验，指针必合法
-        unsafe { &self.0.as_ref().1 }
+        unsafe { &self.0.as_ref().count }
     }
     
     /// 获取计数的裸指针
@@ -74,20 +368,41 @@ impl<T> InnerFlag<T> {
     /// 外部应当永远不会调用到此方法
     ///
     /// # Panics
-    /// 计数溢出时 panic
+    /// 计数溢出 [`FlagCell::MAX_REFS`] 时 panic
     pub fn inc_ref_count(&self) -> isize  {
         let cell = self.count_ref();
         let val = cell.get();
-        if val == isize::MAX || val == isize::MIN + 1 {
-            panic!("Flag 计数溢出，最大允许 {}",isize::MAX);
+        if val == FlagCell::<T>::MAX_REFS || val == -FlagCell::<T>::MAX_REFS {
+            panic!("flag-cell: reference count overflow (max isize::MAX)");
         };
         // 不用判断0，因为0时数据会被销毁，从而永远不可能在0时调用该方法
         debug_assert_ne!(val, 0);
         let new_val = if val > 0 {val + 1} else {val - 1};
         cell.set(new_val);
+        self.check_ref_watch(new_val.abs());
         new_val
     }
     
+    /// 计数由阈值增长到阈值加一时调用回调
+    ///
+    /// 回调执行期间回调被暂时取出，其中再次增加计数不会重入
+    fn check_ref_watch(&self, total: isize) {
+        let Some(slot) = self.extra().map(|extra| &extra.ref_watch) else {
+            return;
+        };
+        let Some(watch) = slot.take() else {
+            return;
+        };
+        if total == watch.threshold.saturating_add(1) {
+            (watch.callback)(total);
+        }
+        // 回调中若设置了新的回调，以新的为准
+        let mut slot = slot.borrow_mut();
+        if slot.is_none() {
+            *slot = Some(watch);
+        }
+    }
+    
     /// 使引用数量减一，返回当前数量
     ///
     /// 外部应当永远不会调用到此方法
@@ -98,7 +413,7 @@ impl<T> InnerFlag<T> {
         let cell = self.count_ref();
         let val = cell.get();
         if val == 0 {
-            panic!("Flag 计数为0时递减计数");
+            panic!("flag-cell: reference count decremented at zero");
         }
         debug_assert_ne!(val, 0);
         let new_val = if val > 0 {val - 1} else {val + 1};
@@ -128,11 +443,197 @@ impl<T> InnerFlag<T> {
         }
     }
     
+    /// 获取强租约数量
+    #[inline]
+    pub fn leases(&self) -> usize {
+        self.extra().map_or(0, |extra| extra.leases.get())
+    }
+    
+    /// 设置强租约数量
+    pub fn set_leases(&self, leases: usize) {
+        self.extra_or_init().leases.set(leases);
+    }
+    
+    /// 获取弱计数的引用
+    #[inline]
+    pub fn weak_ref(&self) -> &Cell<usize> {
+        // SAFETY: 同 count_ref
+        unsafe { &self.0.as_ref().weak }
+    }
+    
+    /// 当前是否存在强租约
+    #[inline]
+    pub fn is_leased(&self) -> bool {
+        self.leases() > 0
+    }
+    
+    /// 获取代数
+    ///
+    /// 仅在复活、转为新的所有者或重新放入数据时加一，用于区分同一块内存上的不同逻辑对象
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        // SAFETY: 同 count_ref
+        unsafe { self.0.as_ref().generation.get() }
+    }
+    
+    /// 使代数加一
+    #[inline]
+    pub fn bump_generation(&self) {
+        // SAFETY: 同 count_ref
+        let generation = unsafe { &self.0.as_ref().generation };
+        generation.set(generation.get().wrapping_add(1));
+    }
+    
+    /// 获取变更版本，与代数相互独立
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.extra().map_or(0, |extra| extra.version.get())
+    }
+    
+    /// 使变更版本加一
+    pub fn bump_version(&self) {
+        let version = &self.extra_or_init().version;
+        version.set(version.get().wrapping_add(1));
+    }
+    
+    /// 当前是否设置了某一模式标记位
+    #[inline]
+    pub fn has_flag(&self, flag: u8) -> bool {
+        // SAFETY: 同 count_ref
+        unsafe { self.0.as_ref().flags.get() & flag != 0 }
+    }
+    
+    /// 设置某一模式标记位
+    #[inline]
+    pub fn set_flag(&self, flag: u8) {
+        // SAFETY: 同 count_ref
+        let flags = unsafe { &self.0.as_ref().flags };
+        flags.set(flags.get() | flag);
+    }
+    
+    /// 清除某一模式标记位
+    #[inline]
+    pub fn clear_flag(&self, flag: u8) {
+        // SAFETY: 同 count_ref
+        let flags = unsafe { &self.0.as_ref().flags };
+        flags.set(flags.get() & !flag);
+    }
+    
+    /// 获取嵌套禁用深度
+    #[inline]
+    pub fn depth(&self) -> u32 {
+        self.extra().map_or(0, |extra| extra.disable_depth.get())
+    }
+    
+    /// 设置嵌套禁用深度；清零时不为此分配附属元数据
+    pub fn set_depth(&self, depth: u32) {
+        if depth == 0 && self.extra().is_none() {
+            return;
+        }
+        self.extra_or_init().disable_depth.set(depth);
+    }
+    
+    /// 按当前模式逻辑禁用
+    ///
+    /// 嵌套模式下深度加一，仅在深度由0变为1时真正禁用，总是返回 `Some`
+    pub fn disable_logical(&self) -> Option<()> {
+        if !self.has_flag(FLAG_NESTED) {
+            return self.disable();
+        }
+        let val = self.depth();
+        self.set_depth(val.checked_add(1).expect("Flag 嵌套禁用深度溢出"));
+        if val == 0 {
+            self.disable();
+        }
+        Some(())
+    }
+    
+    /// 按当前模式逻辑启用
+    ///
+    /// 嵌套模式下深度减一，仅在深度由1变为0时真正启用；深度已为0时返回 `None`
+    pub fn enable_logical(&self) -> Option<()> {
+        if !self.has_flag(FLAG_NESTED) {
+            return self.enable();
+        }
+        let val = self.depth();
+        if val == 0 {
+            return None;
+        }
+        self.set_depth(val - 1);
+        if val == 1 {
+            self.enable();
+        }
+        Some(())
+    }
+    
+    /// 设置计数阈值回调，替换已有的回调
+    pub fn set_ref_watch(&self, watch: RefCountWatch) {
+        self.extra_or_init().ref_watch.replace(Some(watch));
+    }
+    
+    /// 获取不常用的元数据，尚未分配时返回 `None`
+    #[inline]
+    pub fn extra(&self) -> Option<&HeaderExtra> {
+        // SAFETY: 同 count_ref
+        unsafe { self.0.as_ref().extra.get().map(|extra| &**extra) }
+    }
+    
+    /// 获取不常用的元数据，尚未分配时分配
+    pub fn extra_or_init(&self) -> &HeaderExtra {
+        // SAFETY: 同 count_ref
+        unsafe { self.0.as_ref().extra.get_or_init(Box::default) }
+    }
+    
+    /// 获取用户标签
+    #[inline]
+    pub fn tag(&self) -> u32 {
+        self.extra().map_or(0, |extra| extra.tag.get())
+    }
+    
+    /// 设置用户标签；设为0时不为此分配附属元数据
+    pub fn set_tag(&self, tag: u32) {
+        if tag == 0 && self.extra().is_none() {
+            return;
+        }
+        self.extra_or_init().tag.set(tag);
+    }
+    
+    /// 记录一次成功借用的调用栈，仅在启用 `debug_borrow_tracking` feature 时生效
+    #[inline]
+    pub fn record_borrow(&self) {
+        #[cfg(feature = "debug_borrow_tracking")]
+        // SAFETY: 同 count_ref
+        unsafe {
+            *self.0.as_ref().last_borrow.borrow_mut() = Some(Backtrace::force_capture());
+        }
+    }
+    
+    /// 最近一次成功借用的调用栈的文本形式
+    #[cfg(feature = "debug_borrow_tracking")]
+    pub fn last_borrow_string(&self) -> Option<String> {
+        // SAFETY: 同 count_ref
+        let last_borrow = unsafe { self.0.as_ref().last_borrow.borrow() };
+        last_borrow.as_ref().map(|bt| bt.to_string())
+    }
+    
+    /// 以借用冲突为由 panic，并附带最近一次成功借用的调用栈
+    #[cfg(feature = "debug_borrow_tracking")]
+    #[cold]
+    #[track_caller]
+    pub fn borrow_conflict(&self, reason: &str) -> ! {
+        // SAFETY: 同 count_ref
+        let last_borrow = unsafe { self.0.as_ref().last_borrow.borrow() };
+        match last_borrow.as_ref() {
+            Some(bt) => panic!("{reason}; [debug_borrow_tracking] conflicting borrow taken at:\n{bt}"),
+            None => panic!("{reason}; [debug_borrow_tracking] no borrow recorded"),
+        }
+    }
+    
     /// 获取内部RefCell的只读引用
     #[inline]
     pub unsafe fn as_ref_unchecked(&self) -> &RefCell<ManuallyDrop<T>> {
         // SAFETY: 调用者必须保证指针非空+内存未释放
-        unsafe { &self.0.as_ref().0 }
+        unsafe { &self.0.as_ref().data }
     }
     
     /// 获取内部RefCell的裸指针
@@ -143,9 +644,15 @@ impl<T> InnerFlag<T> {
     
     /// 获取内部核心指针
     #[inline]
-    pub fn inner_ptr(&self) -> NonNull<(RefCell<ManuallyDrop<T>>, Cell<isize>)> {
+    pub fn inner_ptr(&self) -> NonNull<FlagHeader<T>> {
         self.0
     }
+    
+    /// 获取内部核心指针的地址，用作分配的身份标识
+    #[inline]
+    pub fn addr(&self) -> usize {
+        self.0.as_ptr().addr()
+    }
 }
 
 /// 带标记+引用计数+内部可变性的智能容器
@@ -153,37 +660,273 @@ impl<T> InnerFlag<T> {
 ///
 /// 确保在安全使用时，Cell存在即内部数据存在。
 /// 正常使用时，逻辑上是不会有人再访问已经释放的数据的，因为确保访问者死完了数据才会释放。
+///
+/// 数据可以是零大小类型：`FlagCell<()>` 即一个纯粹的、可被多个 [`FlagRef`] 观察的启用标记。
+///
+/// # 与 `Rc` 的术语对照
+///
+/// 原生名称之外提供一组与 [`Rc`](std::rc::Rc) 同名的别名，行为完全相同：
+///
+/// | `Rc` 术语 | 本 crate |
+/// | --- | --- |
+/// | `Rc::downgrade` | [`FlagCell::downgrade`] = [`FlagCell::flag_borrow`] |
+/// | `Weak::upgrade` | [`FlagRef::upgrade`] = [`FlagRef::resurrect`]；仅需阻止禁用时用 [`FlagRef::upgrade_strong`] |
+/// | `Rc::strong_count` | [`FlagCell::strong_count`]、[`FlagRef::strong_count`] |
+/// | `Rc::weak_count` | [`FlagCell::weak_count`]、[`FlagRef::weak_count`]，即 [`WeakFlagRef`] 的数量 |
+///
+/// 注意 `FlagRef` 虽对应 `Weak`，却会保活内部数据；真正不保活的弱引用是 [`WeakFlagRef`]。
 #[repr(transparent)]
 #[derive(Debug)]
-pub struct FlagCell<T>(InnerFlag<T>);
+pub struct FlagCell<T: ?Sized>(InnerFlag<T>);
 
-impl<T> FlagCell<T> {
-    fn from_inner(ptr: NonNull<(RefCell<ManuallyDrop<T>>, Cell<isize>)>) -> Self {
-        Self(InnerFlag(ptr))
+impl<T: ?Sized> FlagCell<T> {
+    /// 同一数据上 `FlagCell` 与 [`FlagRef`] 的总数上限，超出时 panic
+    pub const MAX_REFS: isize = isize::MAX;
+    
+    fn from_inner(ptr: NonNull<FlagHeader<T>>) -> Self {
+        let cell = Self(InnerFlag(ptr));
+        cell.0.set_flag(FLAG_OWNED);
+        cell
     }
     
     /// 获取当前 [`FlagRef`] 引用数量
     pub fn ref_count(&self) -> isize {
         // 减去自己
+        debug_assert!(self.0.has_flag(FLAG_OWNED));
         debug_assert!(self.0.ref_count() >= 1);
         self.0.ref_count() - 1
     }
     
+    /// 获取保活内部数据的持有者总数，即自己、所有 [`FlagRef`] 与 [`CountLease`]，对标 [`Rc::strong_count`](std::rc::Rc::strong_count)
+    pub fn strong_count(&self) -> isize {
+        self.0.ref_count()
+    }
+    
+    /// 获取核心结构中原始的计数值：绝对值为持有者总数，禁用时为负
+    ///
+    /// 仅用于调试计数问题，属于实现细节，编码方式可能随版本变化。
+    pub fn raw_count(&self) -> isize {
+        self.0.count_ref().get()
+    }
+    
     /// 获取数据是否逻辑启用
     pub fn is_enabled(&self) -> bool {
         self.0.is_enabled()
     }
     
+    /// 获取当前代数
+    ///
+    /// 每次成为新的逻辑对象时加一：经由 [`FlagRef::resurrect`] 复活、[`FlagRef::into_owner`]
+    /// 或 [`reinit`](#method.reinit)。[`FlagHandle`] 与 [`FlagRef::key`] 依据代数识别逻辑对象，
+    /// 内部值的修改不会改变代数，修改计数见 [`version`](#method.version)。
+    pub fn generation(&self) -> u64 {
+        self.0.generation()
+    }
+    
+    /// 获取当前变更版本，初始为0
+    ///
+    /// 每次 [`borrow_mut_tracked`](#method.borrow_mut_tracked) 的守卫释放时、
+    /// 每次 [`replace_notify`](#method.replace_notify) 时加一，供订阅者轮询变化。与代数相互独立。
+    pub fn version(&self) -> u64 {
+        self.0.version()
+    }
+    
+    /// 生成一行便于日志与测试使用的描述
+    ///
+    /// 格式为 `FlagCell@{地址} enabled={} refs={} gen={}`，其中 `refs` 同 [`ref_count`](#method.ref_count)
+    pub fn describe(&self) -> String {
+        format!(
+            "FlagCell@{:#x} enabled={} refs={} gen={}",
+            self.0.addr(),
+            self.is_enabled(),
+            self.ref_count(),
+            self.generation()
+        )
+    }
+    
+    /// 获取用户标签，初始为0
+    ///
+    /// 可用于标记种类、版本等。与代数无关，完全由使用者控制。首次设置非零标签时才分配附属元数据，见 [`HEADER_SIZE`]。
+    pub fn tag(&self) -> u32 {
+        self.0.tag()
+    }
+    
+    /// 设置用户标签，详见 [`tag`](#method.tag)
+    pub fn set_tag(&self, tag: u32) {
+        self.0.set_tag(tag);
+    }
+    
+    /// 设置计数阈值回调：持有者总数（`FlagCell` 与 [`FlagRef`]）由 `threshold` 增长到 `threshold + 1` 时调用 `f`
+    ///
+    /// `f` 的参数为当前总数。用于在运行时发现计数无界增长，例如内部数据持有指回自身的 `FlagRef`
+    /// 形成循环而导致泄漏。每次越过阈值都会调用一次；再次调用本方法将替换之前的回调。
+    pub fn warn_on_ref_count_over(&self, threshold: isize, f: impl Fn(isize) + 'static) {
+        self.0.set_ref_watch(RefCountWatch {
+            threshold,
+            callback: Box::new(f),
+        });
+    }
+    
+    /// 一次性获取引用数量、启用状态与代数
+    ///
+    /// 详见 [`FlagSnapshot`]
+    pub fn snapshot(&self) -> FlagSnapshot {
+        FlagSnapshot {
+            ref_count: self.ref_count(),
+            enabled: self.is_enabled(),
+            generation: self.generation(),
+        }
+    }
+    
+    /// 获取当前状态的变更令牌，之后可用 [`has_changed_since`](#method.has_changed_since) 检查是否有变更
+    ///
+    /// 令牌基于变更版本与代数，因此只有经由 [`borrow_mut_tracked`](#method.borrow_mut_tracked)、
+    /// [`replace_notify`](#method.replace_notify) 的修改与复活会被察觉，普通的 [`borrow_mut`](#method.borrow_mut) 不会。
+    pub fn change_token(&self) -> ChangeToken {
+        ChangeToken {
+            generation: self.generation(),
+            version: self.version(),
+        }
+    }
+    
+    /// 自取得 `token` 以来是否发生过变更，详见 [`change_token`](#method.change_token)
+    pub fn has_changed_since(&self, token: ChangeToken) -> bool {
+        self.change_token() != token
+    }
+    
     /// 将数据逻辑启用
+    ///
+    /// 嵌套模式下仅使禁用深度减一，深度归零时才真正启用，详见 [`new_nested`](#method.new_nested)
+    ///
+    /// 内部数据已被 [`drop_payload`](#method.drop_payload) 析构时无法启用，返回 `None`
     pub fn enable(&self) -> Option<()> {
-        self.0.enable()
+        if !self.has_payload() {
+            return None;
+        }
+        self.0.enable_logical()
     }
     
     /// 将数据逻辑禁用
     ///
     /// 这将禁止所有对应 [`FlagRef`] 使用内部数据，直到调用 [`enable`]
+    ///
+    /// 若当前存在 [`StrongLease`]，禁用失败，返回 `None`
+    ///
+    /// 嵌套模式下使禁用深度加一，详见 [`new_nested`](#method.new_nested)
     pub fn disable(&self) -> Option<()> {
-        self.0.disable()
+        if self.0.is_leased() {
+            return None;
+        }
+        self.0.disable_logical()
+    }
+    
+    /// 仅当启用状态等于 `current` 时，将其设为 `new`
+    ///
+    /// 成功返回 `Ok(之前的状态)`，状态不符返回 `Err(实际状态)`。引用数量不受影响。
+    ///
+    /// 若 `new` 为 `false` 但当前存在 [`StrongLease`]，视为失败，返回 `Err(true)`；
+    /// 若 `new` 为 `true` 但内部数据已被析构，视为失败，返回 `Err(false)`。
+    /// 嵌套模式下，设为启用时禁用深度清零，设为禁用时深度置为1。
+    pub fn compare_exchange_enabled(&self, current: bool, new: bool) -> Result<bool, bool> {
+        let actual = self.is_enabled();
+        if actual != current {
+            return Err(actual);
+        }
+        if actual == new {
+            return Ok(actual);
+        }
+        if new {
+            if !self.has_payload() {
+                return Err(actual);
+            }
+            self.0.set_depth(0);
+            self.0.enable();
+        } else {
+            if self.0.is_leased() {
+                return Err(actual);
+            }
+            if self.0.has_flag(FLAG_NESTED) {
+                self.0.set_depth(1);
+            }
+            self.0.disable();
+        }
+        Ok(actual)
+    }
+    
+    /// 获取当前禁用深度
+    ///
+    /// 嵌套模式下为尚未配对 `enable` 的 `disable` 次数；非嵌套模式下，禁用时为1，启用时为0。
+    pub fn disable_depth(&self) -> u32 {
+        if self.0.has_flag(FLAG_NESTED) {
+            self.0.depth()
+        } else {
+            u32::from(!self.is_enabled())
+        }
+    }
+    
+    /// 严格模式下数据已禁用
+    fn is_strict_disabled(&self) -> bool {
+        self.0.has_flag(FLAG_STRICT) && !self.is_enabled()
+    }
+    
+    /// 严格模式下数据已禁用时 panic
+    #[track_caller]
+    fn check_strict(&self) {
+        if self.is_strict_disabled() {
+            panic!("borrow of disabled FlagCell");
+        }
+    }
+    
+    /// 已中毒时 panic
+    #[track_caller]
+    fn check_poison(&self) {
+        if self.is_poisoned() {
+            panic!("borrow of poisoned FlagCell");
+        }
+    }
+    
+    /// 是否已中毒，详见 [`new_poisoning`](#method.new_poisoning)
+    pub fn is_poisoned(&self) -> bool {
+        self.0.has_flag(FLAG_POISONED)
+    }
+    
+    /// 清除中毒状态，详见 [`new_poisoning`](#method.new_poisoning)
+    ///
+    /// 调用者应当先确认内部值处于一致状态。
+    pub fn clear_poison(&self) {
+        self.0.clear_flag(FLAG_POISONED);
+    }
+    
+    /// 已冻结时 panic
+    #[track_caller]
+    fn check_frozen(&self) {
+        if self.is_frozen() {
+            panic!("mutable borrow of frozen FlagCell");
+        }
+    }
+    
+    /// 冻结内部值，此后禁止一切经由本 crate API 的修改，只读借用不受影响
+    ///
+    /// 冻结后：
+    /// - [`borrow_mut`](#method.borrow_mut)、[`replace`](#method.replace)、[`scope`](#method.scope)、
+    ///   [`atomic_update`](#method.atomic_update) 以及基于它们的修改方法 panic；
+    /// - [`try_borrow_mut`](#method.try_borrow_mut) 返回 `None`，[`try_replace`](#method.try_replace) 返回 `Err`，
+    ///   [`try_borrow_mut_result`](#method.try_borrow_mut_result) 返回 `Err`；
+    /// - [`FlagRef::try_borrow_mut`] 返回 `Conflict`，[`FlagRef::try_borrow_mut_detailed`] 返回 [`FlagBorrowError::Frozen`]。
+    ///
+    /// 经由 `Deref` 直接操作内部 [`RefCell`] 不受冻结约束。消费自身的 [`unwrap`](#method.unwrap) 不受影响。
+    pub fn freeze(&self) {
+        self.0.set_flag(FLAG_FROZEN);
+    }
+    
+    /// 解除冻结，详见 [`freeze`](#method.freeze)
+    pub fn unfreeze(&self) {
+        self.0.clear_flag(FLAG_FROZEN);
+    }
+    
+    /// 是否已冻结，详见 [`freeze`](#method.freeze)
+    pub fn is_frozen(&self) -> bool {
+        self.0.has_flag(FLAG_FROZEN)
     }
     
     /// Immutably borrows the wrapped value.
@@ -196,8 +939,17 @@ impl<T> FlagCell<T> {
     /// Panics if the value is currently mutably borrowed. For a non-panicking variant, use
     /// [`try_borrow`](#method.try_borrow).
     ///
+    /// 严格模式下数据已禁用时也会 panic，详见 [`new_strict`](#method.new_strict)。
+    ///
+    /// 借用冲突时的 panic 信息为 [`BorrowConflict`] 的文本，可由 [`catch_borrow_conflict`] 捕获为结构化的错误。
+    ///
+    #[track_caller]
     pub fn borrow(&self) -> Ref<'_, T> {
-        Ref::map(self.deref().borrow(),|md| md.deref())
+        self.check_strict();
+        self.check_poison();
+        let r = self.borrow_raw();
+        self.0.record_borrow();
+        Ref::map(r,|md| md.deref())
     }
     
     /// Mutably borrows the wrapped value.
@@ -211,11 +963,171 @@ impl<T> FlagCell<T> {
     /// Panics if the value is currently borrowed. For a non-panicking variant, use
     /// [`try_borrow_mut`](#method.try_borrow_mut).
     ///
+    /// 严格模式下数据已禁用时也会 panic，详见 [`new_strict`](#method.new_strict)。
+    ///
+    /// 借用冲突时的 panic 信息为 [`BorrowConflict`] 的文本，可由 [`catch_borrow_conflict`] 捕获为结构化的错误。
+    ///
+    #[track_caller]
     pub fn borrow_mut(&self) -> RefMut<'_, T> {
-        RefMut::map(self.deref().borrow_mut(),|md| md.deref_mut())
+        self.check_strict();
+        self.check_poison();
+        self.check_frozen();
+        let r = self.borrow_mut_raw();
+        self.0.record_borrow();
+        RefMut::map(r,|md| md.deref_mut())
     }
     
-    /// Immutably borrows the wrapped value, returning an error if the value is currently mutably
+    /// 不作模式检查地借用内部 `RefCell`，冲突时因 [`BorrowConflict`] panic
+    #[track_caller]
+    fn borrow_raw(&self) -> Ref<'_, ManuallyDrop<T>> {
+        match self.deref().try_borrow() {
+            Ok(r) => r,
+            Err(_) => self.borrow_conflict(BorrowKind::Shared),
+        }
+    }
+    
+    /// 不作模式检查地可变借用内部 `RefCell`，冲突时因 [`BorrowConflict`] panic
+    #[track_caller]
+    fn borrow_mut_raw(&self) -> RefMut<'_, ManuallyDrop<T>> {
+        match self.deref().try_borrow_mut() {
+            Ok(r) => r,
+            Err(_) => self.borrow_conflict(BorrowKind::Mutable),
+        }
+    }
+    
+    /// 同 [`borrow_mut_raw`](#method.borrow_mut_raw)，守卫在 panic 展开期间 drop 时按中毒模式使数据中毒
+    #[track_caller]
+    fn borrow_mut_poisoning(&self) -> PoisonRefMut<'_, T> {
+        PoisonRefMut {
+            inner: RefMut::map(self.borrow_mut_raw(), |md| md.deref_mut()),
+            flag: &self.0,
+        }
+    }
+    
+    /// 以 [`BorrowConflict`] 的文本 panic，详见 [`raise_borrow_conflict`]
+    #[cold]
+    #[track_caller]
+    fn borrow_conflict(&self, requested: BorrowKind) -> ! {
+        // 共享借用只会与可变借用冲突；可变借用冲突时再探测现有借用的种类
+        let held = if requested == BorrowKind::Mutable && self.deref().try_borrow().is_ok() {
+            BorrowKind::Shared
+        } else {
+            BorrowKind::Mutable
+        };
+        raise_borrow_conflict(BorrowConflict {
+            requested,
+            held,
+            generation: self.generation(),
+            #[cfg(feature = "debug_borrow_tracking")]
+            last_borrow: self.0.last_borrow_string(),
+        })
+    }
+    
+    /// 可变借用内部值，并将其拆分为两个互不重叠的可变借用，对标 [`RefMut::map_split`]
+    ///
+    /// 两个守卫均释放后，内部值才可再次借用。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn borrow_mut_split<U: ?Sized, V: ?Sized>(
+        &self,
+        f: impl FnOnce(&mut T) -> (&mut U, &mut V),
+    ) -> (RefMut<'_, U>, RefMut<'_, V>) {
+        RefMut::map_split(self.borrow_mut(), f)
+    }
+    
+    /// 可变借用内部值，并将其拆分为三个互不重叠的可变借用，详见 [`borrow_mut_split`](#method.borrow_mut_split)
+    ///
+    /// 三个守卫均释放后，内部值才可再次借用。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn borrow_mut_split3<A: ?Sized, B: ?Sized, C: ?Sized>(
+        &self,
+        f: impl FnOnce(&mut T) -> (&mut A, &mut B, &mut C),
+    ) -> (RefMut<'_, A>, RefMut<'_, B>, RefMut<'_, C>) {
+        // map_split 只支持二分：第一次拆出 A 与 B，同时记下 C，第二次再从 B 的守卫中拆出 C
+        let mut third: Option<NonNull<C>> = None;
+        let (a, b) = RefMut::map_split(self.borrow_mut(), |t| {
+            // 直接调用 f(t) 需要 C 比闭包的高阶生命周期活得更久，而 map_split 的签名只为 A、B 隐含了该约束，
+            // 编译器会要求 C: 'static（E0310）；因此以局部重借用调用 f，再经由指针恢复 a、b 的生命周期
+            let (a, b, c) = f(&mut *t);
+            let (mut a, mut b) = (NonNull::from(a), NonNull::from(b));
+            third = Some(NonNull::from(c));
+            // SAFETY: 三者均派生自 t，生命周期不超过 t
+            unsafe { (a.as_mut(), b.as_mut()) }
+        });
+        let mut third = third.expect("map_split always calls the closure");
+        let (b, c) = RefMut::map_split(b, |b| {
+            // SAFETY: c 与 a、b 互不重叠（由 f 的签名保证），且来自同一个仍被守卫持有的可变借用
+            (b, unsafe { third.as_mut() })
+        });
+        (a, b, c)
+    }
+    
+    /// 借用内部值，仅当 `f` 返回 `Some` 时得到投影后的借用，对标 [`Ref::filter_map`]
+    ///
+    /// 适用于只需借用枚举某一变体内部值等情形；`f` 返回 `None` 时借用随即释放。
+    ///
+    /// # Panics
+    /// 同 [`borrow`](#method.borrow)
+    #[track_caller]
+    pub fn borrow_filter_map<U: ?Sized>(&self, f: impl FnOnce(&T) -> Option<&U>) -> Option<Ref<'_, U>> {
+        Ref::filter_map(self.borrow(), f).ok()
+    }
+    
+    /// 可变借用内部值，仅当 `f` 返回 `Some` 时得到投影后的可变借用，对标 [`RefMut::filter_map`]
+    ///
+    /// # Panics
+    /// 同 [`borrow_mut`](#method.borrow_mut)
+    #[track_caller]
+    pub fn borrow_mut_filter_map<U: ?Sized>(
+        &self,
+        f: impl FnOnce(&mut T) -> Option<&mut U>,
+    ) -> Option<RefMut<'_, U>> {
+        RefMut::filter_map(self.borrow_mut(), f).ok()
+    }
+    
+    /// 可变借用内部值，守卫 drop 时变更版本加一，用于变更追踪
+    ///
+    /// 无论是否真正修改，每次借用结束都会使 [`version`](#method.version) 加一，
+    /// 观察者只需轮询版本即可发现变化。代数不受影响，此前生成的 [`FlagHandle`] 依然可以升级。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn borrow_mut_tracked(&self) -> TrackedRefMut<'_, T> {
+        TrackedRefMut {
+            inner: self.borrow_mut(),
+            flag: &self.0,
+        }
+    }
+    
+    /// 可变借用内部值，中毒模式下若守卫在 panic 展开期间被 drop，则使数据中毒
+    ///
+    /// 非中毒模式下与 [`borrow_mut`](#method.borrow_mut) 相同，详见 [`new_poisoning`](#method.new_poisoning)。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed or poisoned.
+    ///
+    #[track_caller]
+    pub fn borrow_mut_guarded(&self) -> PoisonRefMut<'_, T> {
+        PoisonRefMut {
+            inner: self.borrow_mut(),
+            flag: &self.0,
+        }
+    }
+    
+    /// Immutably borrows the wrapped value, returning an error if the value is currently mutably
     /// borrowed.
     ///
     /// The borrow lasts until the returned `Ref` exits scope. Multiple immutable borrows can be
@@ -224,7 +1136,10 @@ impl<T> FlagCell<T> {
     /// This is the non-panicking variant of [`borrow`](#method.borrow).
     ///
     pub fn try_borrow(&self) -> Option<Ref<'_, T>> {
-        self.deref().try_borrow().ok().map(|r| {
+        if !self.has_payload() || self.is_strict_disabled() || self.is_poisoned() {
+            return None;
+        }
+        self.deref().try_borrow().ok().inspect(|_| self.0.record_borrow()).map(|r| {
             Ref::map(r, |md| md.deref()) // 解包ManuallyDrop
         })
     }
@@ -238,28 +1153,172 @@ impl<T> FlagCell<T> {
     /// This is the non-panicking variant of [`borrow_mut`](#method.borrow_mut).
     ///
     pub fn try_borrow_mut(&self) -> Option<RefMut<'_, T>> {
-        self.deref().try_borrow_mut().ok().map(|r| {
+        if !self.has_payload() || self.is_strict_disabled() || self.is_poisoned() || self.is_frozen() {
+            return None;
+        }
+        self.deref().try_borrow_mut().ok().inspect(|_| self.0.record_borrow()).map(|r| {
             RefMut::map(r, |md| md.deref_mut()) // 解包ManuallyDrop
         })
     }
     
-    /// Creates a new `FlagCell` containing `value`.
-    pub fn new(value: T) -> Self {
-        // 对标 std::rc，leak 解放堆内存生命周期，手动管理释放
-        Self::from_inner(
-            NonNull::from(
-                Box::leak(Box::new(
-                    (RefCell::new(ManuallyDrop::new(value)), Cell::new(1)
-                    )
-                ))
-            )
-        )
+    /// 最多尝试 `attempts` 次 [`try_borrow`](#method.try_borrow)，两次尝试之间调用 `between`
+    ///
+    /// 用于回调较多的单线程代码：借用可能被调用栈上方的帧暂时持有，`between` 可用于推进事件循环等，
+    /// 使其有机会释放借用。这不是阻塞锁，不会等待也不会自旋；`between` 无法让借用释放时，
+    /// 全部尝试失败并返回 `None`。`attempts` 为 0 时直接返回 `None`。
+    pub fn try_borrow_with_retry(&self, attempts: usize, mut between: impl FnMut()) -> Option<Ref<'_, T>> {
+        for i in 0..attempts {
+            if i > 0 {
+                between();
+            }
+            if let Some(r) = self.try_borrow() {
+                return Some(r);
+            }
+        }
+        None
+    }
+    
+    /// 借用内部值并以 `f` 计算结果，无法借用时（详见 [`try_borrow`](#method.try_borrow)）返回 `default`
+    ///
+    /// 借用仅在 `f` 执行期间存在，适合读取少量派生值而不必持有守卫。
+    pub fn borrow_map_or<U>(&self, default: U, f: impl FnOnce(&T) -> U) -> U {
+        self.try_borrow().map_or(default, |r| f(&r))
+    }
+    
+    /// 仅当当前值与 `prev` 不同时返回可变借用，相同时返回 `None`
+    ///
+    /// 先共享借用内部值进行比较，释放后再可变借用，适用于差异比较循环中跳过未变化的项。
+    /// 无法借用时（详见 [`try_borrow`](#method.try_borrow) 与 [`try_borrow_mut`](#method.try_borrow_mut)）同样返回 `None`。
+    pub fn borrow_mut_if_changed(&self, prev: &T) -> Option<RefMut<'_, T>>
+    where
+        T: PartialEq,
+    {
+        if *self.try_borrow()? == *prev {
+            return None;
+        }
+        self.try_borrow_mut()
+    }
+    
+    /// 若当前存在任何借用则 panic，用于在测试结束时断言借用状态干净
+    ///
+    /// 被 `mem::forget` 的 `Ref`/`RefMut` 守卫会使借用状态永久残留，之后的借用将莫名 panic；
+    /// 在合适的位置调用本方法可尽早发现。启用 `debug_borrow_tracking` feature 时，
+    /// panic 信息附带最近一次成功借用的调用栈，通常即被遗忘的守卫的来源。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed, mutably or immutably.
+    ///
+    #[track_caller]
+    pub fn assert_no_active_borrows(&self) {
+        if self.deref().try_borrow_mut().is_err() {
+            #[cfg(feature = "debug_borrow_tracking")]
+            self.0.borrow_conflict("FlagCell still has an active borrow");
+            #[cfg(not(feature = "debug_borrow_tracking"))]
+            panic!("FlagCell still has an active borrow");
+        }
+    }
+    
+    /// 可重入的只读借用：已存在共享借用时，总能再得到一个共享守卫
+    ///
+    /// 与 [`try_borrow`](#method.try_borrow) 行为相同，仅在存在可变借用时返回 `None`，从不 panic。
+    /// 适用于递归的只读遍历：只要整条调用链都使用本方法而非 `borrow_mut`，嵌套读取就不会冲突。
+    ///
+    /// ```
+    /// use flag_cell::FlagCell;
+    ///
+    /// struct Tree {
+    ///     value: u32,
+    ///     children: Vec<FlagCell<Tree>>,
+    /// }
+    ///
+    /// fn sum(cell: &FlagCell<Tree>) -> u32 {
+    ///     let node = cell.try_read_reentrant().expect("tree is being mutated");
+    ///     node.value + node.children.iter().map(sum).sum::<u32>()
+    /// }
+    ///
+    /// let leaf = FlagCell::new(Tree { value: 2, children: Vec::new() });
+    /// let root = FlagCell::new(Tree { value: 1, children: vec![leaf] });
+    /// let _outer = root.try_read_reentrant().unwrap();
+    /// assert_eq!(sum(&root), 3);
+    /// ```
+    pub fn try_read_reentrant(&self) -> Option<Ref<'_, T>> {
+        self.try_borrow()
+    }
+    
+    /// Immutably borrows the wrapped value, returning an error if the value is currently mutably
+    /// borrowed.
+    ///
+    /// 与 [`try_borrow`](#method.try_borrow) 相同，但原样返回 [`RefCell`] 的 [`BorrowError`]。
+    ///
+    pub fn try_borrow_result(&self) -> Result<Ref<'_, T>, BorrowError> {
+        if !self.has_payload() || self.is_poisoned() {
+            return Err(synthetic_borrow_error());
+        }
+        self.deref().try_borrow().inspect(|_| self.0.record_borrow()).map(|r| {
+            Ref::map(r, |md| md.deref()) // 解包ManuallyDrop
+        })
+    }
+    
+    /// Mutably borrows the wrapped value, returning an error if the value is currently borrowed.
+    ///
+    /// 与 [`try_borrow_mut`](#method.try_borrow_mut) 相同，但原样返回 [`RefCell`] 的 [`BorrowMutError`]。
+    ///
+    pub fn try_borrow_mut_result(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+        if !self.has_payload() || self.is_poisoned() || self.is_frozen() {
+            return Err(synthetic_borrow_mut_error());
+        }
+        self.deref().try_borrow_mut().inspect(|_| self.0.record_borrow()).map(|r| {
+            RefMut::map(r, |md| md.deref_mut()) // 解包ManuallyDrop
+        })
+    }
+    
+    /// 不经由 `Ref` 守卫，直接得到内部值的引用，生命周期与 `&self` 绑定
+    ///
+    /// # Panics
+    /// 若调用时内部值正被可变借用，panic。
+    ///
+    /// # SAFETY
+    /// 与 [`RefCell::try_borrow_unguarded`] 相同：返回的引用存活期间，
+    /// 调用者必须保证没有任何可变借用（包括经由 [`FlagRef`] 的可变借用）发生。
+    #[track_caller]
+    pub unsafe fn borrow_unguarded(&self) -> &T {
+        // SAFETY: 调用者保证引用存活期间不存在可变借用
+        match unsafe { self.deref().try_borrow_unguarded() } {
+            Ok(md) => md.deref(),
+            Err(_) => panic!("called `FlagCell::borrow_unguarded()` on a mutably borrowed value"),
+        }
     }
     
     /// 得到内部[`RefCell`]的引用
+    ///
+    /// # Panics
+    /// 内部数据已被 [`drop_payload`](#method.drop_payload) 析构时 panic
+    #[track_caller]
     pub fn as_ref_cell_ref(&self) -> &RefCell<ManuallyDrop<T>> {
-        // SAFETY：确保正常使用时，FlagCell 存在即数据存在
-        unsafe { self.0.as_ref_unchecked() }
+        self.deref()
+    }
+    
+    /// 得到去掉 [`ManuallyDrop`] 包装的内部 [`RefCell`] 的引用，可传给接受 `&RefCell<T>` 的已有接口
+    ///
+    /// 与直接借用一样绕过启用状态、冻结与中毒检查。经由它 `replace`/`take` 得到的旧值归调用者所有，
+    /// 留在 `RefCell` 中的新值仍由 `FlagCell` 负责析构。
+    ///
+    /// 返回的引用借用 `&self`，存活期间无法调用需要 `&mut self` 的 [`drop_payload`](#method.drop_payload)。
+    ///
+    /// 注意：`RefCell` 为 `repr(Rust)`，语言并不保证 `RefCell<ManuallyDrop<T>>` 与 `RefCell<T>` 布局相同，
+    /// 本方法依赖当前标准库实现中二者布局一致，调试构建下会校验大小、对齐与数据位置。
+    pub fn as_plain_refcell(&self) -> &RefCell<T> {
+        let inner = self.as_ref_cell_ref();
+        let ptr = ptr::from_ref(inner) as *const RefCell<T>;
+        // SAFETY: 假定上述两种 RefCell 布局一致（ManuallyDrop<T> 为 repr(transparent)，
+        // 而 RefCell 对不同 T 的字段排布取决于 T 的布局，二者的 T 布局相同），但这并非语言保证；
+        // 数据由 FlagCell 保活，且 drop_payload 需要 &mut self，引用不会越过数据的析构
+        let plain = unsafe { &*ptr };
+        debug_assert_eq!(mem::size_of_val(plain), mem::size_of_val(inner));
+        debug_assert_eq!(mem::align_of_val(plain), mem::align_of_val(inner));
+        debug_assert_eq!(plain.as_ptr().cast::<u8>(), inner.as_ptr().cast::<u8>());
+        plain
     }
     
     /// 得到内部[`RefCell`]的指针
@@ -268,6 +1327,25 @@ impl<T> FlagCell<T> {
         unsafe { self.0.as_ptr_unchecked() }
     }
     
+    /// 获取一个仅保活内部数据的 [`CountLease`]
+    ///
+    /// 租约与 [`FlagRef`] 一样计入引用数量，即使 `FlagCell` 被销毁，内部数据也会存活到租约释放，
+    /// 但租约不提供任何访问途径。
+    pub fn lease(&self) -> CountLease<T> {
+        self.0.inc_ref_count();
+        CountLease(InnerFlag(self.0.inner_ptr()))
+    }
+    
+    /// 生成一个 [`WeakFlagRef`]
+    ///
+    /// 与 [`flag_borrow`](#method.flag_borrow) 不同，弱引用不保活内部数据：
+    /// 所有 `FlagCell` 与 `FlagRef` 销毁后内部数据随即析构，之后 [`WeakFlagRef::upgrade`] 返回 `Empty`。
+    pub fn clone_ref_weak(&self) -> WeakFlagRef<T> {
+        let weak = self.0.weak_ref();
+        weak.set(weak.get() + 1);
+        WeakFlagRef(InnerFlag(self.0.inner_ptr()))
+    }
+    
     /// 生成一个 [`FlagRef`]
     ///
     pub fn flag_borrow(&self) -> FlagRef<T> {
@@ -276,347 +1354,2714 @@ impl<T> FlagCell<T> {
         ref_flag
     }
     
+    /// 可变借用内部迭代器并取出下一项
+    ///
+    /// 内部数据已被 [`drop_payload`](#method.drop_payload) 析构时返回 `DataDropped`，
+    /// 数据已禁用时返回 `Disabled`，正被借用时返回 `Conflict`，迭代结束时返回 `Empty`。
+    ///
+    /// 这不是 [`Iterator`] 的实现（借用语义使其无法实现），只是便于共享游标的便捷方法，
+    /// 配合 [`FlagRef::next`] 可让多个持有者从同一迭代器中依次取值。
+    pub fn next(&self) -> FlagRefOption<T::Item>
+    where
+        T: Iterator,
+    {
+        if !self.has_payload() {
+            return FlagRefOption::DataDropped;
+        }
+        if !self.is_enabled() {
+            return FlagRefOption::Disabled;
+        }
+        match self.try_borrow_mut() {
+            Some(mut it) => it.next().map_or(FlagRefOption::Empty, FlagRefOption::Some),
+            None => FlagRefOption::Conflict,
+        }
+    }
     
-    /// Replaces the wrapped value with a new one, returning the old value,
-    /// without deinitializing either one.
+    /// 可变借用内部集合，并以 `iter` 扩展之
     ///
-    /// This function corresponds to [`mem::replace`].
+    /// 适用于 `FlagCell<Vec<T>>` 等内部值实现了 [`Extend`] 的情形。
     ///
     /// # Panics
     ///
     /// Panics if the value is currently borrowed.
     ///
-    /// For non-panicking variant , see [`try_replace`](#method.try_replace).
-    ///
-    pub fn replace(&self, value: T) -> T {
-        // SAFETY: replace返回所有权，且这个ManuallyDrop马上被丢弃
-        unsafe { ManuallyDrop::take(&mut self.deref().replace(ManuallyDrop::new(value))) }
+    #[track_caller]
+    pub fn extend_inner<A>(&self, iter: impl IntoIterator<Item = A>)
+    where
+        T: Extend<A>,
+    {
+        self.borrow_mut().extend(iter);
     }
     
-    /// Replaces the wrapped value with a new one, returning the old value,
-    /// without deinitializing either one.
+    /// 可变借用内部值并执行可能失败的修改 `f`，原样返回其结果
     ///
-    /// This function corresponds to [`mem::replace`].
+    /// 本方法不做回滚：`f` 返回 `Err` 时，由 `f` 自己负责使内部值保持有效状态。
+    /// 需要真正回滚时使用 [`try_map_in_place_cloned`](#method.try_map_in_place_cloned)。
     ///
-    /// 如果当前存在引用，返回Err返还传入值
+    /// # Panics
     ///
-    /// This is the non-panicking variant of [`replace`](#method.replace).
+    /// Panics if the value is currently borrowed.
     ///
-    pub fn try_replace(&self, value: T) -> Result<T,T> {
-        // SAFETY: replace返回所有权，且这个ManuallyDrop马上被丢弃
-        unsafe {
-            Ok(ManuallyDrop::take(
-                &mut mem::replace(
-                    match self.deref().try_borrow_mut() {
-                        Ok(v) => {
-                            v
-                        }
-                        Err(_) => {return Err(value)}
-                    }.deref_mut(),
-                    ManuallyDrop::new(value)
-                )
-            ))
-        }
+    #[track_caller]
+    pub fn try_map_in_place<E>(&self, f: impl FnOnce(&mut T) -> Result<(), E>) -> Result<(), E> {
+        f(&mut self.borrow_mut())
     }
     
-    /// 消费自身，返回内部数据，同时禁用
+    /// 在禁用状态下可变借用内部值并执行 `f`，结束后重新启用
+    ///
+    /// 执行期间所有 [`FlagRef::try_borrow`] 都会得到 `Disabled`，因此观察者不会看到修改到一半的值。
+    /// 即使 `f` panic，退出时也会重新启用。
+    ///
+    /// 注意：无论调用前是否处于禁用状态，退出时都会调用一次 [`enable`](#method.enable)；
+    /// 嵌套模式下则是一次配对的 `disable`/`enable`。
     ///
     /// # Panics
-    /// 若当前存在任何引用（包括FlagRef），或被异常禁用，panic。
     ///
-    /// For non-panicking variant , see [`try_unwrap`](#method.try_borrow).
+    /// Panics if the value is currently borrowed, poisoned or frozen, or if a [`StrongLease`]
+    /// prevents disabling it.
     ///
-    pub fn unwrap(self) -> T {
-        let ref_count = self.ref_count();
-        if ref_count > 0 {
-            panic!(
-                "called `FlagCell::unwrap()` on a value with active FlagRef references (ref_count = {})",
-                ref_count
-            );
-        }
-        
-        if !self.is_enabled() {
-            panic!("called `FlagCell::unwrap()` on a disabled FlagCell");
+    /// 中毒模式下 `f` panic 会使数据中毒，详见 [`new_poisoning`](#method.new_poisoning)。
+    ///
+    #[track_caller]
+    pub fn scope<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        self.check_poison();
+        self.check_frozen();
+        if self.0.is_leased() {
+            panic!("scoped update of leased FlagCell");
         }
-        
-        let mut rm = self.as_ref_cell_ref().borrow_mut();
         self.disable();
-        unsafe {
-            ManuallyDrop::take(rm.deref_mut())
-        }
-        // self 将在此处被drop。
+        let _guard = EnableOnDrop(self);
+        // 绕过严格模式检查：此时的禁用是本方法自己造成的
+        f(&mut self.borrow_mut_poisoning())
     }
     
-    /// 消费自身，返回内部数据，同时禁用
+    /// 在禁用状态下修改内部值，结束后恢复调用前的启用状态
     ///
-    /// 若当前存在任何引用（包括FlagRef），或被异常禁用，返还Self
+    /// 执行 `f` 期间观察者经由 [`FlagRef::try_borrow`] 只会得到 `Disabled`，之后则看到修改完成的值，
+    /// 不会看到修改到一半的中间状态。与 [`scope`](#method.scope) 的区别：
+    /// - 调用前已禁用时不改变启用状态，退出时也不会启用；
+    /// - 嵌套模式下只做一次配对的 `disable`/`enable`，禁用深度与调用前相同；
+    /// - 引用计数不变，即使 `f` panic 也会恢复启用状态。
     ///
-    /// This is the non-panicking variant of [`unwrap`](#method.unwrap).
+    /// # Panics
     ///
-    pub fn try_unwrap(self) -> Result<T, Self> {
-        let ref_count = self.ref_count();
-        if !self.is_enabled() || ref_count > 0 {
-            return Err(self);
-        }
-        
-        let r = self.as_ref_cell_ref().try_borrow_mut();
-        let mut rm = match r {
-            Ok(ref_mut) => {ref_mut}
-            Err(_) => {
-                // 如果不在此分支内drop r，编译器会认为 r 会活得更久，从而拒绝给出 self
-                // 很奇葩，我都return了他还活个啥？
-                drop(r);
-                return Err(self);
+    /// Panics if the value is currently borrowed, poisoned or frozen, or if a [`StrongLease`]
+    /// prevents disabling it.
+    ///
+    #[track_caller]
+    pub fn atomic_update(&self, f: impl FnOnce(&mut T)) {
+        self.check_poison();
+        self.check_frozen();
+        let _guard = if self.is_enabled() {
+            if self.disable().is_none() {
+                panic!("atomic update of leased FlagCell");
             }
+            Some(EnableOnDrop(self))
+        } else {
+            None
         };
-        self.disable();
-        unsafe {
-            Ok(ManuallyDrop::take(rm.deref_mut()))
+        // 绕过严格模式检查：此时的禁用是本方法自己造成的，或调用前即已禁用
+        f(&mut self.borrow_mut_poisoning());
+    }
+    
+    /// 内部数据是否存在，即未被 [`drop_payload`](#method.drop_payload) 析构
+    pub fn has_payload(&self) -> bool {
+        !self.0.has_flag(FLAG_DATA_DROPPED)
+    }
+    
+    /// 原地析构内部数据，但保留 `FlagCell` 本身，返回调用前数据是否存在
+    ///
+    /// 之后 `FlagCell` 处于禁用的“中空”状态，可经由 [`reinit`](#method.reinit) 放入新值，适用于对象池复用。
+    /// 中空期间：
+    /// - `FlagCell` 的 `try_*` 借用方法失败，[`enable`](#method.enable) 返回 `None`，其余借用方法 panic；
+    /// - 所有 [`FlagRef`] 的借用与复活返回 `DataDropped`。
+    ///
+    /// 与销毁 `FlagCell` 一样无视 [`StrongLease`]，必定禁用。
+    ///
+    /// 需要 `&mut self`：经由 `Deref` 或 [`as_plain_refcell`](#method.as_plain_refcell)
+    /// 得到的 `&RefCell` 存活期间无法调用，以免之后经由它读取已析构的数据：
+    ///
+    /// ```compile_fail
+    /// let mut cell = flag_cell::FlagCell::new(String::from("a"));
+    /// let rc = cell.as_plain_refcell();
+    /// cell.drop_payload();
+    /// let _ = rc.borrow();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed, e.g. through a guard obtained from a `FlagRef`.
+    ///
+    #[track_caller]
+    pub fn drop_payload(&mut self) -> bool {
+        if !self.has_payload() {
+            return false;
+        }
+        let Ok(mut guard) = RefCell::try_borrow_mut(&**self) else {
+            panic!("called `FlagCell::drop_payload()` on a borrowed FlagCell");
+        };
+        // 与 compare_exchange_enabled 相同，绕过 disable_logical 时维持“禁用即深度非零”
+        if self.is_enabled() && self.0.has_flag(FLAG_NESTED) {
+            self.0.set_depth(1);
         }
-        // self 将在此处被drop。
+        self.0.disable();
+        // 先设置标记位：析构过程中重入的 FlagRef 只会得到 DataDropped
+        self.0.set_flag(FLAG_DATA_DROPPED);
+        // SAFETY: 已设置标记位，此后任何途径都不会再访问或析构这份数据
+        unsafe { ManuallyDrop::drop(guard.deref_mut()) };
+        true
     }
 }
 
-impl<T> Drop for FlagCell<T> {
-    // 这drop与FlagRef的drop严格互斥
-    fn drop(&mut self) {
-        
-        let ptr = self.0.inner_ptr();
-        
-        self.disable();
-        
-        let new_count = self.0.dec_ref_count();
-        if new_count == 0 {
-            // SAFETY: 计数0=无其他引用，可以释放。
-            // new_count 首次归零意味着，内存未曾释放，这是唯一释放点。
-            unsafe {
-                // 修复：先手动析构ManuallyDrop包裹的T，再析构外层结构
-                let refcell = &mut (*ptr.as_ptr()).0;
-                let mut_man_drop = RefCell::get_mut(refcell);
-                ManuallyDrop::drop(mut_man_drop);
-                
-                // 析构剩余结构 + 释放内存
-                drop_in_place(ptr.as_ptr());
-                dealloc(
-                    ptr.as_ptr() as *mut u8,
-                    Layout::new::<(RefCell<ManuallyDrop<T>>, Cell<isize>)>()
-                );
+impl<T> FlagCell<T> {
+    /// 以 [`FlagCow`] 形式借用内部值
+    ///
+    /// 返回的 `FlagCow` 初始为 `Borrowed`，需要修改时可通过 [`FlagCow::to_mut`] 转为独立副本。
+    ///
+    /// # Panics
+    /// 同 [`borrow`](#method.borrow)
+    #[track_caller]
+    pub fn borrow_cow(&self) -> FlagCow<'_, T> {
+        FlagCow::Borrowed(self.borrow())
+    }
+    
+    /// 可变借用内部值；存在冲突的共享借用时，改为返回内部值的独立克隆
+    ///
+    /// 用于乐观更新：返回的 [`CowMut`] 两种情况下都解引用为 `T`，
+    /// 克隆上的修改需在冲突解除后通过 [`CowMut::commit`] 写回。
+    ///
+    /// # Panics
+    /// 内部值已被可变借用、无法克隆时因 [`BorrowConflict`] panic；
+    /// 其余同 [`borrow_mut`](#method.borrow_mut)。
+    #[track_caller]
+    pub fn borrow_mut_or_clone(&self) -> CowMut<'_, T>
+    where
+        T: Clone,
+    {
+        self.check_strict();
+        self.check_poison();
+        self.check_frozen();
+        let state = match self.deref().try_borrow_mut() {
+            Ok(r) => {
+                self.0.record_borrow();
+                CowMutState::Borrowed(RefMut::map(r, |md| md.deref_mut()))
             }
-        }
+            Err(_) => match self.deref().try_borrow() {
+                Ok(r) => CowMutState::Owned(T::clone(&r)),
+                Err(_) => self.borrow_conflict(BorrowKind::Mutable),
+            },
+        };
+        CowMut { cell: self, state }
     }
-}
-
-impl<T> Deref for FlagCell<T> {
-    type Target = RefCell<ManuallyDrop<T>>;
     
-    fn deref(&self) -> &Self::Target {
-        // SAFETY：FlagCell存在则内存有效、指针合法
-        unsafe { self.0.as_ref_unchecked() }
+    /// Creates a new `FlagCell` containing `value`.
+    pub fn new(value: T) -> Self {
+        // 对标 std::rc，leak 解放堆内存生命周期，手动管理释放
+        Self::from_inner(
+            NonNull::from(
+                Box::leak(Box::new(FlagHeader::new(value)))
+            )
+        )
     }
-}
-
-// impl<T> !Send for FlagCell<T> {}
-// impl<T> !Sync for FlagCell<T> {}
+    
+    /// 以 `f` 的返回值创建 `FlagCell`
+    ///
+    /// 值在分配完成后才构造并直接写入，优化器通常可以省去栈上的中间副本，但不作保证；
+    /// 需要保证时使用 [`new_in_place`](#method.new_in_place)。
+    pub fn new_with(f: impl FnOnce() -> T) -> Self {
+        // SAFETY: 闭包总是写入完整的值
+        unsafe { Self::new_in_place(|slot| slot.write(f())) }
+    }
+    
+    /// 先分配内存，再由 `f` 将值直接写入数据所在位置，避免大型 `T` 经由栈复制
+    ///
+    /// 若 `f` panic，已分配的内存会被释放，不会析构任何数据。
+    ///
+    /// # SAFETY
+    /// `f` 正常返回时必须已完整初始化传入的指针所指的 `T`。
+    pub unsafe fn new_in_place(f: impl FnOnce(*mut T)) -> Self {
+        const { assert!(mem::size_of::<FlagHeader<[MaybeUninit<T>; 1]>>() == mem::size_of::<FlagHeader<T>>()) };
+        const { assert!(mem::align_of::<FlagHeader<[MaybeUninit<T>; 1]>>() == mem::align_of::<FlagHeader<T>>()) };
+        
+        /// f panic 时释放尚未初始化完成的内存
+        struct DeallocOnUnwind(*mut u8, Layout);
+        impl Drop for DeallocOnUnwind {
+            fn drop(&mut self) {
+                // SAFETY: 内存由 alloc 以相同布局分配，且尚未交出
+                unsafe { dealloc(self.0, self.1) }
+            }
+        }
+        
+        let layout = Layout::new::<FlagHeader<T>>();
+        // SAFETY: 核心结构的元数据字段保证布局大小非零
+        let raw = unsafe { alloc(layout) };
+        if raw.is_null() {
+            handle_alloc_error(layout);
+        }
+        let guard = DeallocOnUnwind(raw, layout);
+        // 与 new_uninit_slice 相同，零长数组版本与长度为1的版本共享前缀布局：
+        // 只写入元数据与空的 RefCell，不在栈上构造任何与 T 等大的临时值
+        let uninit = raw.cast::<FlagHeader<[MaybeUninit<T>; 1]>>();
+        // SAFETY: 内存有效且对齐；MaybeUninit 的数据无需初始化
+        let slot = unsafe {
+            raw.cast::<FlagHeader<[MaybeUninit<T>; 0]>>().write(FlagHeader::new([]));
+            (*uninit).data.as_ptr().cast::<T>()
+        };
+        f(slot);
+        mem::forget(guard);
+        // SAFETY: 调用者保证数据已初始化；[MaybeUninit<T>; 1] 与 T 布局相同
+        Self::from_inner(unsafe { NonNull::new_unchecked(raw.cast::<FlagHeader<T>>()) })
+    }
+    
+    /// 以调用者提供的内存中的核心结构创建 `FlagCell`，最终由 `dealloc` 而非全局分配器释放该内存
+    ///
+    /// 适用于自行管理的内存，例如 bump 分配器或 FFI 提供的内存。最后一个持有者销毁时，
+    /// 先析构内部数据与核心结构，再以同一指针调用一次 `dealloc`。
+    ///
+    /// `dealloc` 记录在全局分配器上的附属元数据中，而非核心结构本身，不增加其他 `FlagCell` 的开销。
+    ///
+    /// # SAFETY
+    /// - `ptr` 必须以 `FlagHeader<T>` 的布局对齐，且指向经由 [`FlagHeader::new`] 初始化、从未交给其他 `FlagCell` 的核心结构；
+    /// - 在 `dealloc` 被调用之前，该内存必须保持有效且不被他人访问。
+    pub unsafe fn from_raw_header(ptr: NonNull<FlagHeader<T>>, dealloc: fn(NonNull<FlagHeader<T>>)) -> Self {
+        let erased = ErasedDealloc {
+            call: call_dealloc::<T>,
+            func: dealloc as *const (),
+        };
+        let cell = Self::from_inner(ptr);
+        cell.0.extra_or_init().dealloc.set(Some(erased));
+        cell
+    }
+    
+    /// 创建一个嵌套模式的 `FlagCell`
+    ///
+    /// 嵌套模式下，每次 `disable` 使禁用深度加一，每次 `enable` 使其减一，
+    /// 仅当深度为0时数据才处于启用状态。这样多个互不相关的子系统各自禁用时，
+    /// 其中一个的 `enable` 不会覆盖其他子系统的 `disable`。
+    ///
+    /// `FlagCell` 销毁时无视深度直接禁用；复活时深度清零。
+    pub fn new_nested(value: T) -> Self {
+        let cell = Self::new(value);
+        cell.0.set_flag(FLAG_NESTED);
+        cell
+    }
+    
+    /// 创建一个严格模式的 `FlagCell`
+    ///
+    /// 默认情况下 `FlagCell` 自身的借用无视启用状态；严格模式下，数据禁用时
+    /// [`borrow`](#method.borrow)/[`borrow_mut`](#method.borrow_mut) panic，
+    /// [`try_borrow`](#method.try_borrow)/[`try_borrow_mut`](#method.try_borrow_mut) 返回 `None`。
+    ///
+    /// `try_borrow_result` 系列原样返回 [`RefCell`] 的错误，无法表达禁用，因此不受严格模式影响。
+    pub fn new_strict(value: T) -> Self {
+        let cell = Self::new(value);
+        cell.0.set_flag(FLAG_STRICT);
+        cell
+    }
+    
+    /// 创建一个中毒模式的 `FlagCell`
+    ///
+    /// 类似 [`Mutex`](std::sync::Mutex) 的中毒：若 [`borrow_mut_guarded`](#method.borrow_mut_guarded)
+    /// 得到的守卫在 panic 展开期间被 drop，或 [`scope`](#method.scope)、[`atomic_update`](#method.atomic_update)
+    /// 的闭包 panic，内部值可能处于不一致状态，此时设置中毒标记。中毒后：
+    /// - [`borrow`](#method.borrow)/[`borrow_mut`](#method.borrow_mut) panic；
+    /// - [`try_borrow`](#method.try_borrow) 系列返回 `None`/`Err`；
+    /// - [`FlagRef::try_borrow`] 系列返回 `Conflict`，详细版本返回 [`FlagBorrowError::Poisoned`]。
+    ///
+    /// 调用 [`clear_poison`](#method.clear_poison) 后恢复。
+    ///
+    /// 普通的 [`borrow_mut`](#method.borrow_mut) 返回标准库的 `RefMut`，无法在其 drop 时挂接检查，
+    /// 因此不会使数据中毒；需要中毒保护的修改应使用上述方法。
+    pub fn new_poisoning(value: T) -> Self {
+        let cell = Self::new(value);
+        cell.0.set_flag(FLAG_POISONING);
+        cell
+    }
+    
+    /// 创建一个延迟析构模式的 `FlagCell`
+    ///
+    /// 当最后一个持有者销毁时，内部数据不会立即析构，而是加入线程局部队列，
+    /// 直到调用 [`drain_deferred`] 时才真正析构并释放内存，适用于析构开销大、需要避开热路径的数据。
+    ///
+    /// 要求 `T: 'static`，因为析构时机不再受原作用域约束。
+    ///
+    /// 线程退出时队列中剩余的数据依次析构；此过程中新加入的延迟数据（如嵌套的延迟 `FlagCell`）会立即析构。
+    pub fn new_deferred(value: T) -> Self
+    where
+        T: 'static,
+    {
+        let cell = Self::new(value);
+        cell.0.set_flag(FLAG_DEFERRED);
+        cell
+    }
+    
+    /// 创建一个 `FlagCell`，同时返回它的第一个 [`FlagRef`]
+    ///
+    /// 等价于先 [`new`](#method.new) 再 [`flag_borrow`](#method.flag_borrow)。
+    ///
+    /// ```
+    /// use flag_cell::FlagCell;
+    ///
+    /// let (cell, flag_ref) = FlagCell::new_with_ref(5);
+    /// assert_eq!(cell.ref_count(), 1);
+    /// assert_eq!(*flag_ref.try_borrow().unwrap(), 5);
+    /// ```
+    pub fn new_with_ref(value: T) -> (FlagCell<T>, FlagRef<T>) {
+        let cell = Self::new(value);
+        let flag_ref = cell.flag_borrow();
+        (cell, flag_ref)
+    }
+    
+    /// 生成一个擦除了类型的 [`FlagRef<dyn Any>`](FlagRef)，可存入异构的注册表
+    ///
+    /// 与 [`flag_borrow`](#method.flag_borrow) 相同地计入引用数量，之后可经由 [`FlagRef::downcast`] 取回具体类型。
+    pub fn flag_borrow_any(&self) -> FlagRef<dyn Any>
+    where
+        T: Any,
+    {
+        let ptr: NonNull<FlagHeader<dyn Any>> = self.0.inner_ptr();
+        let ref_flag = FlagRef(InnerFlag(ptr));
+        ref_flag.0.inc_ref_count();
+        ref_flag
+    }
+    
+    /// Replaces the wrapped value with a new one, returning the old value,
+    /// without deinitializing either one.
+    ///
+    /// This function corresponds to [`mem::replace`].
+    ///
+    /// 直接经由内部 [`RefCell`] 操作，与启用状态（包括严格模式）无关：禁用时同样替换并返回旧值。
+    /// 若需要仅在启用时替换，使用 [`replace_enabled_only`](#method.replace_enabled_only)。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    /// For non-panicking variant , see [`try_replace`](#method.try_replace).
+    ///
+    #[track_caller]
+    pub fn replace(&self, value: T) -> T {
+        self.check_frozen();
+        // SAFETY: replace返回所有权，且这个ManuallyDrop马上被丢弃
+        unsafe { ManuallyDrop::take(&mut mem::replace(&mut *self.borrow_mut_raw(), ManuallyDrop::new(value))) }
+    }
+    
+    /// 同 [`replace`](#method.replace)，替换后变更版本加一
+    ///
+    /// 版本变化使之前取得的 [`ChangeToken`] 报告变更（见 [`has_changed_since`](#method.has_changed_since)），
+    /// 订阅者因此能察觉到值被替换；普通的 `replace` 不改变版本。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed, or if it is frozen.
+    ///
+    pub fn replace_notify(&self, value: T) -> T {
+        let old = self.replace(value);
+        self.0.bump_version();
+        old
+    }
+    
+    /// 仅当数据启用时替换内部值，返回 `Ok(旧值)`；数据已禁用时不作修改，返回 `Err(value)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is enabled and currently borrowed.
+    ///
+    pub fn replace_enabled_only(&self, value: T) -> Result<T, T> {
+        if !self.is_enabled() {
+            return Err(value);
+        }
+        Ok(self.replace(value))
+    }
+    
+    /// 以 `T::default()` 替换内部值，返回旧值，等价于 `replace(T::default())`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    pub fn replace_with_default(&self) -> T
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+    
+    /// Replaces the wrapped value with a new one, returning the old value,
+    /// without deinitializing either one.
+    ///
+    /// This function corresponds to [`mem::replace`].
+    ///
+    /// 如果当前存在引用，返回Err返还传入值
+    ///
+    /// 与 [`replace`](#method.replace) 相同，与启用状态无关。
+    ///
+    /// This is the non-panicking variant of [`replace`](#method.replace).
+    ///
+    pub fn try_replace(&self, value: T) -> Result<T,T> {
+        if !self.has_payload() || self.is_frozen() {
+            return Err(value);
+        }
+        // SAFETY: replace返回所有权，且这个ManuallyDrop马上被丢弃
+        unsafe {
+            Ok(ManuallyDrop::take(
+                &mut mem::replace(
+                    match self.deref().try_borrow_mut() {
+                        Ok(v) => {
+                            v
+                        }
+                        Err(_) => {return Err(value)}
+                    }.deref_mut(),
+                    ManuallyDrop::new(value)
+                )
+            ))
+        }
+    }
+    
+    /// 与 `other` 所指向的内部值交换
+    ///
+    /// `other` 为空、已禁用或任一方正被借用时，返回对应的变体且不作修改。
+    /// 两者指向同一目标时视为无操作，返回 `Some(())`。
+    pub fn swap_with(&self, other: &FlagRef<T>) -> FlagRefOption<()> {
+        dangling_then_return!(other.0.inner_ptr().as_ptr(), FlagRefOption::Empty);
+        if self.0.addr() == other.0.addr() {
+            return FlagRefOption::Some(());
+        }
+        let mut theirs = match other.try_borrow_mut() {
+            FlagRefOption::Some(r) => r,
+            fail => return fail.map(|_| unreachable!()),
+        };
+        match self.try_borrow_mut() {
+            Some(mut ours) => {
+                mem::swap(ours.deref_mut(), theirs.deref_mut());
+                FlagRefOption::Some(())
+            }
+            None => FlagRefOption::Conflict,
+        }
+    }
+    
+    /// 由当前值计算新值，`f` 返回 `Ok(new)` 时替换并返回 `Ok(旧值)`，返回 `Err(e)` 时不作修改并返回 `Err(e)`
+    ///
+    /// 适用于新值依赖旧值且可能被校验拒绝的更新。`f` 执行期间持有可变借用。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn try_replace_with<E>(&self, f: impl FnOnce(&T) -> Result<T, E>) -> Result<T, E> {
+        let mut rm = self.borrow_mut();
+        let new = f(&rm)?;
+        Ok(mem::replace(rm.deref_mut(), new))
+    }
+    
+    /// 仅当 `predicate` 对当前值返回 `true` 时，以 `value` 替换之
+    ///
+    /// 替换成功返回 `Ok(旧值)`，否则不作修改并返回 `Err(value)`。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn replace_if<P: FnOnce(&T) -> bool>(&self, predicate: P, value: T) -> Result<T, T> {
+        let mut rm = self.borrow_mut();
+        if predicate(&rm) {
+            Ok(mem::replace(rm.deref_mut(), value))
+        } else {
+            Err(value)
+        }
+    }
+    
+    /// 取出内部集合的全部元素并扩展到 `sink` 中，内部集合留为 `T::default()`（即空集合）
+    ///
+    /// 适用于 `FlagCell<Vec<Event>>` 等事件队列式的批量处理。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn drain_into<S: Extend<T::Item>>(&self, sink: &mut S)
+    where
+        T: Default + IntoIterator,
+    {
+        let taken = mem::take(self.borrow_mut().deref_mut());
+        sink.extend(taken);
+    }
+    
+    /// 可变借用内部值并执行可能失败的修改 `f`，`f` 返回 `Err` 时恢复为调用前的值
+    ///
+    /// 调用前先克隆一份快照，失败时以快照覆盖 `f` 留下的值。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn try_map_in_place_cloned<E>(&self, f: impl FnOnce(&mut T) -> Result<(), E>) -> Result<(), E>
+    where
+        T: Clone,
+    {
+        let mut rm = self.borrow_mut();
+        let snapshot = T::clone(&rm);
+        f(&mut rm).inspect_err(|_| *rm = snapshot)
+    }
+    
+    /// 克隆一份当前值并立即释放借用，返回与 `FlagCell` 无关的独立副本
+    ///
+    /// 无法借用时（详见 [`try_borrow`](#method.try_borrow)）返回 `None`。
+    pub fn snapshot_value(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.try_borrow().map(|r| T::clone(&r))
+    }
+    
+    /// 以当前值覆盖 `dst`，经由 [`Clone::clone_from`] 复用 `dst` 已有的资源（如 `Vec` 的容量）
+    ///
+    /// 无法借用时（详见 [`try_borrow`](#method.try_borrow)）不修改 `dst`，返回 `false`。
+    pub fn clone_into(&self, dst: &mut T) -> bool
+    where
+        T: Clone,
+    {
+        match self.try_borrow() {
+            Some(r) => {
+                dst.clone_from(&r);
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// 仅当 `predicate` 返回 `true` 时，以 `T::default()` 替换当前值并返回旧值，对标 [`Option::take_if`]
+    ///
+    /// `predicate` 可修改内部值；返回 `false` 时返回 `None`，修改仍会保留。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn take_if<P: FnOnce(&mut T) -> bool>(&self, predicate: P) -> Option<T>
+    where
+        T: Default,
+    {
+        let mut rm = self.borrow_mut();
+        if predicate(&mut rm) {
+            Some(mem::take(rm.deref_mut()))
+        } else {
+            None
+        }
+    }
+    
+    /// 消费自身，返回内部数据，同时禁用
+    ///
+    /// # Panics
+    /// 若当前存在任何引用（包括FlagRef），或被异常禁用，panic。
+    ///
+    /// For non-panicking variant , see [`try_unwrap`](#method.try_borrow).
+    ///
+    #[track_caller]
+    pub fn unwrap(self) -> T {
+        let ref_count = self.ref_count();
+        if ref_count > 0 {
+            panic!(
+                "called `FlagCell::unwrap()` on a value with active FlagRef references (ref_count = {})",
+                ref_count
+            );
+        }
+        
+        if !self.is_enabled() {
+            panic!("called `FlagCell::unwrap()` on a disabled FlagCell");
+        }
+        
+        let value = unsafe {
+            ManuallyDrop::take(self.as_ref_cell_ref().borrow_mut().deref_mut())
+        };
+        // 数据已被取出，不能再经由 drop 析构；计数归零使 WeakFlagRef 无法再升级
+        let ptr = self.0.inner_ptr();
+        self.0.count_ref().set(0);
+        mem::forget(self);
+        // SAFETY: 已确认无其他引用，且数据已被取出
+        unsafe { release_weak(ptr) };
+        value
+    }
+    
+    /// 消费自身，返回内部数据，同时禁用
+    ///
+    /// 若当前存在任何引用（包括FlagRef），或被异常禁用，返还Self
+    ///
+    /// 返还的 Self 不作任何修改，内部数据完好，可继续借用，待引用清空后再次解包。
+    ///
+    /// This is the non-panicking variant of [`unwrap`](#method.unwrap).
+    ///
+    pub fn try_unwrap(self) -> Result<T, Self> {
+        self.try_unwrap_reason().map_err(|(cell, _)| cell)
+    }
+    
+    /// 消费自身，返回内部数据，同时禁用
+    ///
+    /// 与 [`try_unwrap`](#method.try_unwrap) 相同，但失败时一并返回原因，详见 [`UnwrapError`]。
+    ///
+    pub fn try_unwrap_reason(self) -> Result<T, (Self, UnwrapError)> {
+        let ref_count = self.ref_count();
+        if ref_count > 0 {
+            return Err((self, UnwrapError::HasRefs(ref_count)));
+        }
+        if !self.is_enabled() {
+            return Err((self, UnwrapError::Disabled));
+        }
+        
+        if self.as_ref_cell_ref().try_borrow_mut().is_err() {
+            return Err((self, UnwrapError::Borrowed));
+        }
+        // 上面已确认可以可变借用，此处不会 panic
+        let value = unsafe {
+            ManuallyDrop::take(self.as_ref_cell_ref().borrow_mut().deref_mut())
+        };
+        // 数据已被取出，不能再经由 drop 析构；计数归零使 WeakFlagRef 无法再升级
+        let ptr = self.0.inner_ptr();
+        self.0.count_ref().set(0);
+        mem::forget(self);
+        // SAFETY: 已确认无其他引用，且数据已被取出
+        unsafe { release_weak(ptr) };
+        Ok(value)
+    }
+    
+    /// 消费自身，不作任何检查直接返回内部数据
+    ///
+    /// [`unwrap`](#method.unwrap) 的无检查版本，跳过引用数量、启用状态与借用状态的校验。
+    ///
+    /// # SAFETY
+    /// 调用者必须保证：不存在任何 [`FlagRef`]、[`CountLease`] 或借用守卫，且数据处于启用状态。
+    /// 违反时其他持有者将访问已被取出的数据，导致未定义行为。
+    pub unsafe fn into_inner_unchecked(self) -> T {
+        let ptr = self.0.inner_ptr();
+        // SAFETY: 调用者保证唯一持有且无借用
+        let value = unsafe { ManuallyDrop::take(&mut *(*ptr.as_ptr()).data.as_ptr()) };
+        self.0.count_ref().set(0);
+        mem::forget(self);
+        // SAFETY: 数据已被取出，且调用者保证无其他持有者
+        unsafe { release_weak(ptr) };
+        value
+    }
+    
+    /// 为 [`drop_payload`](#method.drop_payload) 之后的中空 `FlagCell` 放入新值并启用
+    ///
+    /// 与 [`FlagRef::resurrect`] 一样视为新的逻辑对象：代数加一，嵌套禁用深度清零。
+    /// 内部数据仍存在时不作修改，返回 `Err(value)`。
+    pub fn reinit(&self, value: T) -> Result<(), T> {
+        if self.has_payload() {
+            return Err(value);
+        }
+        // SAFETY: 中空状态下没有任何借用，也不会有人访问这块数据；该位置的旧值已被析构
+        unsafe { (*self.as_ref_cell_ptr()).as_ptr().write(ManuallyDrop::new(value)) };
+        self.0.clear_flag(FLAG_DATA_DROPPED);
+        self.0.set_depth(0);
+        self.0.enable();
+        self.0.bump_generation();
+        Ok(())
+    }
+    
+    /// 消费自身，无论是否存在 [`FlagRef`] 都取走并返回内部数据
+    ///
+    /// 与 [`unwrap`](#method.unwrap) 不同，仍有其他持有者时不会 panic：数据被取走后，
+    /// 核心结构照常存活到最后一个持有者销毁，在此期间所有 `FlagRef` 的借用与复活都返回 `DataDropped`
+    /// （[`FlagRef::try_borrow_detailed`] 等返回 [`FlagBorrowError::DataDropped`]）。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed, e.g. through a guard obtained from a `FlagRef`.
+    ///
+    #[track_caller]
+    pub fn force_unwrap(self) -> T {
+        let Ok(mut guard) = self.deref().try_borrow_mut() else {
+            panic!("called `FlagCell::force_unwrap()` on a borrowed FlagCell");
+        };
+        // SAFETY: 取出后立即设置标记位，此后任何途径都不会再访问或析构这份数据
+        let value = unsafe { ManuallyDrop::take(guard.deref_mut()) };
+        drop(guard);
+        self.0.set_flag(FLAG_DATA_DROPPED);
+        // 正常 drop：禁用并释放自己的计数，最后一个持有者销毁时只释放内存
+        drop(self);
+        value
+    }
+    
+    /// 消费自身，将内部数据移入新分配的 [`Rc`]，用于在不再需要标记与复活时转为普通的共享值
+    ///
+    /// 布局不同，数据会被移动到新的内存中；此后原有的 [`WeakFlagRef`] 无法再升级。
+    /// 条件与 [`try_unwrap`](#method.try_unwrap) 相同，不满足时原样返还 `Err(self)`。
+    pub fn into_rc(self) -> Result<Rc<T>, Self> {
+        self.try_unwrap().map(Rc::new)
+    }
+}
+
+impl<T: Copy> FlagCell<T> {
+    /// 返回内部值的副本，对标 [`Cell::get`]
+    ///
+    /// 与 [`replace`](#method.replace) 以及下面的 [`set`](#method.set)、[`swap`](#method.swap)
+    /// 一同构成 `Copy` 类型的 `Cell` 式接口，内部仍经由借用实现，不会产生长期存在的守卫。
+    ///
+    /// # Panics
+    /// 同 [`borrow`](#method.borrow)
+    #[track_caller]
+    pub fn get(&self) -> T {
+        *self.borrow()
+    }
+    
+    /// 设置内部值，对标 [`Cell::set`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed, or if it is frozen.
+    ///
+    #[track_caller]
+    pub fn set(&self, value: T) {
+        self.replace(value);
+    }
+    
+    /// 交换两个 `FlagCell` 的内部值，对标 [`Cell::swap`]
+    ///
+    /// 两者为同一 `FlagCell` 时不作任何操作。与启用状态无关。
+    ///
+    /// # Panics
+    ///
+    /// Panics if either value is currently borrowed, or if either is frozen.
+    ///
+    #[track_caller]
+    pub fn swap(&self, other: &Self) {
+        if self.0.addr() == other.0.addr() {
+            return;
+        }
+        // 先检查两者，避免只修改了其中一个
+        self.check_frozen();
+        other.check_frozen();
+        let value = other.replace(self.get());
+        self.set(value);
+    }
+}
+
+impl<T> FlagCell<Option<T>> {
+    /// 若内部为 `None`，以 `f` 的结果初始化，随后借用内部的 `T`
+    ///
+    /// 内部已有值时不会调用 `f`。可变借用在取得返回的共享借用前即已释放，不会与之冲突。
+    /// 若 `f` 执行期间内部已被（重入地）初始化，保留已有值并丢弃 `f` 的结果。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed, or currently borrowed when
+    /// initialization is needed.
+    ///
+    #[track_caller]
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> Ref<'_, T> {
+        if self.borrow().is_none() {
+            let value = f();
+            self.borrow_mut().get_or_insert(value);
+        }
+        Ref::map(self.borrow(), |opt| opt.as_ref().expect("initialized above"))
+    }
+}
+
+impl<A, B> FlagCell<(A, B)> {
+    /// 可变借用内部的二元组，拆分为两个元素各自独立的可变借用，详见 [`borrow_mut_split`](#method.borrow_mut_split)
+    ///
+    /// 两个守卫均释放后，内部值才可再次借用。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    ///
+    pub fn borrow_tuple_mut(&self) -> (RefMut<'_, A>, RefMut<'_, B>) {
+        self.borrow_mut_split(|(a, b)| (a, b))
+    }
+}
+
+impl<T, E> FlagCell<Result<T, E>> {
+    /// 内部为 `Ok` 时借用其中的 `T`，否则返回 `None`
+    ///
+    /// # Panics
+    /// 同 [`borrow`](#method.borrow)
+    pub fn borrow_ok(&self) -> Option<Ref<'_, T>> {
+        self.borrow_filter_map(|r| r.as_ref().ok())
+    }
+    
+    /// 内部为 `Err` 时借用其中的 `E`，否则返回 `None`
+    ///
+    /// # Panics
+    /// 同 [`borrow`](#method.borrow)
+    pub fn borrow_err(&self) -> Option<Ref<'_, E>> {
+        self.borrow_filter_map(|r| r.as_ref().err())
+    }
+}
+
+impl<T> FlagCell<Vec<T>> {
+    /// 创建内部为空 `Vec`、预留 `capacity` 容量的 `FlagCell`，对标 [`Vec::with_capacity`]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::new(Vec::with_capacity(capacity))
+    }
+    
+    /// 可变借用内部 `Vec`，在 `at` 处拆分，返回尾部 `[at, len)`，对标 [`Vec::split_off`]
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`, or if the value is currently borrowed.
+    ///
+    #[track_caller]
+    pub fn split_off(&self, at: usize) -> Vec<T> {
+        self.borrow_mut().split_off(at)
+    }
+    
+    /// 借用内部 `Vec` 并投影为切片
+    ///
+    /// # Panics
+    /// 同 [`borrow`](#method.borrow)
+    #[track_caller]
+    pub fn borrow_slice(&self) -> Ref<'_, [T]> {
+        Ref::map(self.borrow(), Vec::as_slice)
+    }
+    
+    /// 可变借用内部 `Vec` 并投影为切片
+    ///
+    /// # Panics
+    /// 同 [`borrow_mut`](#method.borrow_mut)
+    #[track_caller]
+    pub fn borrow_mut_slice(&self) -> RefMut<'_, [T]> {
+        RefMut::map(self.borrow_mut(), Vec::as_mut_slice)
+    }
+    
+    /// 可变借用内部 `Vec` 并释放多余容量，对标 [`Vec::shrink_to_fit`]
+    ///
+    /// # Panics
+    /// 同 [`borrow_mut`](#method.borrow_mut)
+    #[track_caller]
+    pub fn shrink_inner(&self) {
+        self.borrow_mut().shrink_to_fit();
+    }
+}
+
+impl FlagCell<String> {
+    /// 创建内部为空 `String`、预留 `capacity` 容量的 `FlagCell`，对标 [`String::with_capacity`]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::new(String::with_capacity(capacity))
+    }
+    
+    /// 可变借用内部 `String` 并释放多余容量，对标 [`String::shrink_to_fit`]
+    ///
+    /// # Panics
+    /// 同 [`borrow_mut`](#method.borrow_mut)
+    #[track_caller]
+    pub fn shrink_inner(&self) {
+        self.borrow_mut().shrink_to_fit();
+    }
+}
+
+impl<K, V> FlagCell<HashMap<K, V>> {
+    /// 创建内部为空 `HashMap`、至少可容纳 `capacity` 个元素的 `FlagCell`，对标 [`HashMap::with_capacity`]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::new(HashMap::with_capacity(capacity))
+    }
+}
+
+impl<T: ?Sized> FlagCell<Box<T>> {
+    /// 以 `new` 替换内部的 `Box`，返回旧的 `Box`
+    ///
+    /// 只交换 `Box` 指针，被装箱的值不发生移动，因此同样适用于 `Box<dyn Trait>`。
+    /// 与 [`replace`](#method.replace) 相同，与启用状态无关。
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed, or if it is frozen.
+    ///
+    pub fn replace_boxed(&self, new: Box<T>) -> Box<T> {
+        self.replace(new)
+    }
+}
+
+impl<T, const N: usize> FlagCell<[T; N]> {
+    /// 以 `f(i)` 作为第 `i` 个元素创建数组 `FlagCell`，对标 [`core::array::from_fn`]
+    ///
+    /// 基于 [`new_in_place`](#method.new_in_place)，元素逐个直接写入堆上的数组，整个数组不会经由栈移动。
+    /// 若 `f` panic，已写入的元素会被析构，内存随后释放。
+    pub fn from_fn(mut f: impl FnMut(usize) -> T) -> Self {
+        /// f panic 时析构已写入的前缀
+        struct DropPrefix<T>(*mut T, usize);
+        impl<T> Drop for DropPrefix<T> {
+            fn drop(&mut self) {
+                // SAFETY: 前 self.1 个元素已初始化且尚未交出
+                unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.0, self.1)) }
+            }
+        }
+        
+        // SAFETY: 闭包返回前写入了全部 N 个元素
+        unsafe {
+            Self::new_in_place(|slot| {
+                let mut guard = DropPrefix(slot.cast::<T>(), 0);
+                for i in 0..N {
+                    guard.0.add(i).write(f(i));
+                    guard.1 += 1;
+                }
+                mem::forget(guard);
+            })
+        }
+    }
+}
+
+impl<T> FlagCell<[MaybeUninit<T>]> {
+    /// 创建一个长度为 `len`、元素未初始化的切片 `FlagCell`
+    ///
+    /// 核心结构与切片在同一块内存中分配，不需要先构造再复制。
+    /// 通过 [`borrow_mut`](#method.borrow_mut) 写入全部元素后，
+    /// 调用 [`assume_init_slice`](#method.assume_init_slice) 得到 `FlagCell<[T]>`。
+    ///
+    /// `MaybeUninit` 不会析构其内容：若在转换前销毁，已写入的元素会被泄漏，但不会析构未初始化的元素。
+    ///
+    /// # Panics
+    /// 所需内存大小溢出 `isize::MAX` 时 panic
+    pub fn new_uninit_slice(len: usize) -> Self {
+        let layout = slice_header_layout::<MaybeUninit<T>>(len);
+        // SAFETY: 核心结构的元数据字段保证布局大小非零
+        let raw = unsafe { alloc(layout) };
+        if raw.is_null() {
+            handle_alloc_error(layout);
+        }
+        // SAFETY: 零长数组版本与切片版本前缀布局相同，先写入元数据与空的 RefCell，元素保持未初始化
+        unsafe { raw.cast::<FlagHeader<[MaybeUninit<T>; 0]>>().write(FlagHeader::new([])) };
+        let ptr = ptr::slice_from_raw_parts_mut(raw.cast::<MaybeUninit<T>>(), len) as *mut FlagHeader<[MaybeUninit<T>]>;
+        // SAFETY: 上面已确认非空
+        let ptr = unsafe { NonNull::new_unchecked(ptr) };
+        debug_assert_eq!(unsafe { Layout::for_value(ptr.as_ref()) }, layout);
+        Self::from_inner(ptr)
+    }
+    
+    /// 将未初始化的切片 `FlagCell` 转换为 `FlagCell<[T]>`，不作复制
+    ///
+    /// # SAFETY
+    /// 与 [`MaybeUninit::assume_init`] 相同：调用者必须保证所有元素均已初始化。
+    ///
+    /// # Panics
+    /// 仍存在 [`FlagRef`]、[`CountLease`] 或 [`WeakFlagRef`] 时 panic，原因同 [`assume_init`](#method.assume_init)。
+    #[track_caller]
+    pub unsafe fn assume_init_slice(self) -> FlagCell<[T]> {
+        assert!(
+            self.ref_count() == 0 && self.weak_count() == 0,
+            "called `FlagCell::assume_init_slice()` while other holders exist"
+        );
+        let ptr = self.0.inner_ptr();
+        mem::forget(self);
+        // 仅改变元素类型，长度元数据不变，MaybeUninit<T> 与 T 布局相同
+        FlagCell::from_inner(unsafe { NonNull::new_unchecked(ptr.as_ptr() as *mut FlagHeader<[T]>) })
+    }
+}
+
+impl<T> FlagCell<MaybeUninit<T>> {
+    /// 创建一个内部值未初始化的 `FlagCell`
+    ///
+    /// 通过 [`write`](#method.write) 或 [`as_init_ptr`](#method.as_init_ptr) 写入后，
+    /// 调用 [`assume_init`](#method.assume_init) 得到 `FlagCell<T>`。
+    ///
+    /// 与 [`new_uninit_slice`](#method.new_uninit_slice) 相同，若在转换前销毁，已写入的值会被泄漏。
+    pub fn new_uninit() -> Self {
+        // SAFETY: MaybeUninit 无需初始化
+        unsafe { Self::new_in_place(|_| {}) }
+    }
+    
+    /// 获取指向内部未初始化存储的指针，用于分阶段手动初始化
+    ///
+    /// 指针在 `FlagCell` 及其核心结构存活期间有效。
+    ///
+    /// # SAFETY
+    /// 返回的指针本身可以安全获取，但使用时：
+    /// - 写入之前读取是未定义行为；
+    /// - 读写期间不得存在该值的借用守卫。
+    ///
+    /// # Panics
+    /// 内部数据已被析构时 panic
+    #[track_caller]
+    pub fn as_init_ptr(&self) -> *mut T {
+        self.deref().as_ptr().cast::<T>()
+    }
+    
+    /// 向内部存储写入 `value`，不会析构此前写入的值
+    ///
+    /// # Panics
+    /// 同 [`borrow_mut`](#method.borrow_mut)
+    #[track_caller]
+    pub fn write(&self, value: T) {
+        self.borrow_mut().write(value);
+    }
+    
+    /// 将未初始化的 `FlagCell` 转换为 `FlagCell<T>`，不作复制
+    ///
+    /// # SAFETY
+    /// 与 [`MaybeUninit::assume_init`] 相同：调用者必须保证内部值已初始化。
+    ///
+    /// # Panics
+    /// 仍存在 [`FlagRef`]、[`CountLease`] 或 [`WeakFlagRef`] 时 panic：
+    /// 它们仍以 `MaybeUninit<T>` 访问同一份数据，安全代码即可经由它们写入未初始化的值。
+    #[track_caller]
+    pub unsafe fn assume_init(self) -> FlagCell<T> {
+        assert!(
+            self.ref_count() == 0 && self.weak_count() == 0,
+            "called `FlagCell::assume_init()` while other holders exist"
+        );
+        let ptr = self.0.inner_ptr();
+        mem::forget(self);
+        // MaybeUninit<T> 与 T 布局相同
+        FlagCell::from_inner(ptr.cast::<FlagHeader<T>>())
+    }
+}
+
+/// 得到一个 [`BorrowError`]，用于中毒时的 [`FlagCell::try_borrow_result`]
+///
+/// `BorrowError` 无法直接构造，借助一个临时的、已被可变借用的 `RefCell` 产生
+fn synthetic_borrow_error() -> BorrowError {
+    let cell = RefCell::new(());
+    let _guard = cell.borrow_mut();
+    match cell.try_borrow() {
+        Err(e) => e,
+        Ok(_) => unreachable!(),
+    }
+}
+
+/// 得到一个 [`BorrowMutError`]，用于冻结或中毒时的 [`FlagCell::try_borrow_mut_result`]
+///
+/// 同 [`synthetic_borrow_error`]
+fn synthetic_borrow_mut_error() -> BorrowMutError {
+    let cell = RefCell::new(());
+    let _guard = cell.borrow();
+    match cell.try_borrow_mut() {
+        Err(e) => e,
+        Ok(_) => unreachable!(),
+    }
+}
+
+/// [`FlagCell::try_unwrap_reason`] 失败的原因
+///
+/// HasRefs: 仍存在 [`FlagRef`]，附带其数量 <br>
+/// Disabled: 数据当前已禁用 <br>
+/// Borrowed: 数据当前正被借用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnwrapError {
+    HasRefs(isize),
+    Disabled,
+    Borrowed,
+}
+
+impl fmt::Display for UnwrapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnwrapError::HasRefs(n) => write!(f, "FlagCell has {} active FlagRef references", n),
+            UnwrapError::Disabled => f.write_str("FlagCell is disabled"),
+            UnwrapError::Borrowed => f.write_str("FlagCell is currently borrowed"),
+        }
+    }
+}
+
+impl Error for UnwrapError {}
+
+/// drop 时重新启用 `FlagCell`，用于在 unwind 时也能恢复启用状态
+struct EnableOnDrop<'a, T: ?Sized>(&'a FlagCell<T>);
+
+impl<T: ?Sized> Drop for EnableOnDrop<'_, T> {
+    fn drop(&mut self) {
+        self.0.enable();
+    }
+}
+
+impl<T: ?Sized> Drop for FlagCell<T> {
+    // 这drop与FlagRef的drop严格互斥
+    fn drop(&mut self) {
+        
+        let ptr = self.0.inner_ptr();
+        
+        // 所有者销毁时无视强租约，必定禁用
+        self.0.clear_flag(FLAG_OWNED);
+        self.0.disable();
+        
+        let new_count = self.0.dec_ref_count();
+        if new_count == 0 {
+            // SAFETY: 计数0=无其他引用，可以释放。
+            // new_count 首次归零意味着，内存未曾释放，这是唯一释放点。
+            unsafe { release_header(ptr) }
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for FlagCell<T> {
+    type Target = RefCell<ManuallyDrop<T>>;
+    
+    #[track_caller]
+    fn deref(&self) -> &Self::Target {
+        // 中空状态下不能交出指向已析构数据的引用
+        if self.0.has_flag(FLAG_DATA_DROPPED) {
+            panic!("access to FlagCell whose data has been dropped");
+        }
+        // SAFETY：FlagCell存在则内存有效、指针合法，且数据未被析构
+        unsafe { self.0.as_ref_unchecked() }
+    }
+}
+
+impl<T: ?Sized> fmt::Pointer for FlagCell<T> {
+    /// 输出核心结构地址，用于身份调试
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.0.inner_ptr(), f)
+    }
+}
+
+// impl<T> !Send for FlagCell<T> {}
+// impl<T> !Sync for FlagCell<T> {}
+
+/// 同一时刻的引用数量、启用状态与代数
+///
+/// 由 [`FlagCell::snapshot`] 或 [`FlagRef::snapshot`] 获得，
+/// 其中 `ref_count` 的含义与调用方的 `ref_count()` 相同。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagSnapshot {
+    pub ref_count: isize,
+    pub enabled: bool,
+    pub generation: u64,
+}
+
+/// 变更令牌，记录取得时的代数与变更版本
+///
+/// 由 [`FlagCell::change_token`] 获得，配合 [`FlagCell::has_changed_since`] 做脏检查。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeToken {
+    generation: u64,
+    version: u64,
+}
+
+/// 从FlagCell产生的轻量共享引用，可Clone，单线程使用
+///
+/// 刻意不实现 [`PartialEq`]：`FlagRef` 可能为空或已禁用，`==` 究竟比较身份还是值并不明确。
+/// 比较值请使用 [`try_value_eq`](#method.try_value_eq)，判断是否为同一逻辑对象请使用
+/// [`to_handle`](#method.to_handle) 与 [`FlagHandle::same_logical_object`]，或 [`FlagRefSet`]；需要以 `==` 比较身份时，显式包装为 [`Identity`]。
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct FlagRef<T: ?Sized>(InnerFlag<T>);
+
+/// Some: 可借用 <br>
+/// Conflict: 借用冲突，不符合rust借用原则
+/// Empty: 内部为空，即此FlagRef是从new函数创建的
+/// Disabled: 内部数据当前已禁用 <br>
+/// DataDropped: 内部数据已被取走或析构，仅剩核心结构，详见 [`FlagCell::force_unwrap`] 与 [`FlagCell::drop_payload`]
+///
+/// `DataDropped` 依据独立的标记位判断，优先于启用状态检查：即使数据被强制启用（如 [`FlagRef::enable`]），
+/// 也不会借用到已析构的数据。
+#[derive(Debug)]
+pub enum FlagRefOption<T> {
+    Some(T),
+    Conflict,
+    Empty,
+    Disabled,
+    DataDropped,
+}
+
+impl<T> FlagRefOption<T> {
+    /// 解包 FlagRefOption
+    ///
+    /// # Panics
+    /// 若非 `Some` ，panic
+    #[track_caller]
+    pub fn unwrap(self) -> T {
+        if let FlagRefOption::Some(val) = self {
+            val
+        }
+        else {
+            panic!("called `FlagRefOption::unwrap()` on a not `Some` value")
+        }
+    }
+    
+    /// 解包 FlagRefOption，失败时以 `msg` panic
+    ///
+    /// # Panics
+    /// 若非 `Some` ，以 `msg` 加上实际变体 panic
+    #[track_caller]
+    pub fn expect(self, msg: &str) -> T {
+        match self {
+            FlagRefOption::Some(val) => val,
+            FlagRefOption::Conflict => panic!("{msg}: Conflict"),
+            FlagRefOption::Empty => panic!("{msg}: Empty"),
+            FlagRefOption::Disabled => panic!("{msg}: Disabled"),
+            FlagRefOption::DataDropped => panic!("{msg}: DataDropped"),
+        }
+    }
+    
+    /// 将自己转换为原生 `Option` 类型
+    ///
+    /// Some转换为Some，其余全部转换为None
+    pub fn into_option(self) -> Option<T> {
+        self.into()
+    }
+    
+    /// Maps an `FlagRefOption<T>` to `FlagRefOption<U>` by applying a function to a contained value (为`Some`) or returns 原变体 (非`Some`).
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> FlagRefOption<U> {
+        match self{
+            FlagRefOption::Some(v) => FlagRefOption::Some(f(v)),
+            FlagRefOption::Conflict => FlagRefOption::Conflict,
+            FlagRefOption::Empty => FlagRefOption::Empty,
+            FlagRefOption::Disabled => FlagRefOption::Disabled,
+            FlagRefOption::DataDropped => FlagRefOption::DataDropped,
+        }
+    }
+    
+    /// 将两个 `FlagRefOption` 合并为一个元组
+    ///
+    /// 仅当两者均为 `Some` 时返回 `Some((t, u))`。
+    /// 否则返回第一个非 `Some` 的变体：优先返回 `self` 的变体，其次是 `other` 的变体。
+    pub fn zip<U>(self, other: FlagRefOption<U>) -> FlagRefOption<(T, U)> {
+        match (self, other) {
+            (FlagRefOption::Some(t), FlagRefOption::Some(u)) => FlagRefOption::Some((t, u)),
+            (FlagRefOption::Some(_), other) => other.map(|_| unreachable!()),
+            (this, _) => this.map(|_| unreachable!()),
+        }
+    }
+    
+    /// 若 `self` 为 `Some`，返回 `other`，否则返回 `self` 的变体，对标 [`Option::and`]
+    pub fn and<U>(self, other: FlagRefOption<U>) -> FlagRefOption<U> {
+        match self {
+            FlagRefOption::Some(_) => other,
+            this => this.map(|_| unreachable!()),
+        }
+    }
+    
+    /// 仅当两者恰有一个为 `Some` 时返回该 `Some`，对标 [`Option::xor`]
+    ///
+    /// 两者均为 `Some` 时返回 `Conflict`；均非 `Some` 时返回 `self` 的变体。
+    pub fn xor(self, other: Self) -> FlagRefOption<T> {
+        match (self, other) {
+            (FlagRefOption::Some(_), FlagRefOption::Some(_)) => FlagRefOption::Conflict,
+            (FlagRefOption::Some(t), _) => FlagRefOption::Some(t),
+            (_, FlagRefOption::Some(u)) => FlagRefOption::Some(u),
+            (this, _) => this,
+        }
+    }
+}
+
+impl<'a, T: ?Sized> FlagRefOption<Ref<'a, T>> {
+    /// 对借用守卫做投影，保留 `FlagRefOption` 包装
+    ///
+    /// 借助 [`Ref::map`]，例如将结构体的借用投影到其某个字段。
+    pub fn map_deref<U: ?Sized>(self, f: impl FnOnce(&T) -> &U) -> FlagRefOption<Ref<'a, U>> {
+        self.map(|r| Ref::map(r, f))
+    }
+    
+    /// 将自己转换为原生 `Option` 类型，同 [`into_option`](#method.into_option)
+    pub fn or_empty_ref(self) -> Option<Ref<'a, T>> {
+        self.into_option()
+    }
+    
+    /// 为 `Some` 时返回其中的借用，否则（`Disabled`、`Empty`、`Conflict` 与 `DataDropped`）返回静态的 `default`
+    ///
+    /// 两种情况都以 [`BorrowedOrStatic`] 包装，可直接解引用为 `&T`。
+    pub fn unwrap_or_static(self, default: &'static T) -> BorrowedOrStatic<'a, T>
+    where
+        T: 'static,
+    {
+        match self {
+            FlagRefOption::Some(r) => BorrowedOrStatic::Borrowed(r),
+            _ => BorrowedOrStatic::Static(default),
+        }
+    }
+}
+
+impl<T> From<FlagRefOption<T>> for Option<T> {
+    fn from(f: FlagRefOption<T>) -> Option<T> {
+        match f {
+            FlagRefOption::Some(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl<T> FlagRefOption<T> {
+    fn from_borrow(opt: Option<T>) -> Self {
+        opt.map(Self::Some).unwrap_or(Self::Conflict)
+    }
+}
+
+/// [`FlagRef`] 借用失败的详细原因
+///
+/// AlreadyMutablyBorrowed: 内部数据当前已被可变借用 <br>
+/// AlreadyBorrowed: 内部数据当前已被借用（可变借用时） <br>
+/// Empty: 内部为空，即此FlagRef是从new函数创建的 <br>
+/// Disabled: 内部数据当前已禁用 <br>
+/// Frozen: 内部数据已冻结，不可变借用（可变借用时），详见 [`FlagCell::freeze`] <br>
+/// Poisoned: 内部数据已中毒，详见 [`FlagCell::new_poisoning`] <br>
+/// DataDropped: 内部数据已被取走或析构，详见 [`FlagCell::force_unwrap`] 与 [`FlagCell::drop_payload`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagBorrowError {
+    AlreadyMutablyBorrowed,
+    AlreadyBorrowed,
+    Empty,
+    Disabled,
+    Frozen,
+    Poisoned,
+    DataDropped,
+}
+
+impl fmt::Display for FlagBorrowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlagBorrowError::AlreadyMutablyBorrowed => f.write_str("already mutably borrowed"),
+            FlagBorrowError::AlreadyBorrowed => f.write_str("already borrowed"),
+            FlagBorrowError::Empty => f.write_str("empty FlagRef"),
+            FlagBorrowError::Disabled => f.write_str("FlagRef target is disabled"),
+            FlagBorrowError::Frozen => f.write_str("FlagRef target is frozen"),
+            FlagBorrowError::Poisoned => f.write_str("FlagRef target is poisoned"),
+            FlagBorrowError::DataDropped => f.write_str("FlagRef target data has been taken"),
+        }
+    }
+}
+
+impl Error for FlagBorrowError {}
+
+impl From<BorrowError> for FlagBorrowError {
+    fn from(_: BorrowError) -> Self {
+        FlagBorrowError::AlreadyMutablyBorrowed
+    }
+}
+
+impl From<BorrowMutError> for FlagBorrowError {
+    fn from(_: BorrowMutError) -> Self {
+        FlagBorrowError::AlreadyBorrowed
+    }
+}
+
+/// 借用的种类，见 [`BorrowConflict`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BorrowKind {
+    Shared,
+    Mutable,
+}
+
+/// [`FlagCell::borrow`] 等方法借用冲突的详细信息
+///
+/// 冲突时以其 [`Display`](fmt::Display) 文本 panic，与 `RefCell` 相同以 `already borrowed` /
+/// `already mutably borrowed` 开头，默认的 panic hook 可正常打印。
+/// 结构化的信息只能经由 [`catch_borrow_conflict`] 取得：panic 载荷本身只是字符串。
+///
+/// `RefCell` 不公开共享借用的数量，因此只记录现有借用的种类。
+#[derive(Debug, Clone)]
+pub struct BorrowConflict {
+    /// 本次请求的借用种类
+    pub requested: BorrowKind,
+    /// 已存在的、造成冲突的借用种类
+    pub held: BorrowKind,
+    /// 冲突发生时的代数，详见 [`FlagCell::generation`]
+    pub generation: u64,
+    /// 最近一次成功借用的调用栈，仅在启用 `debug_borrow_tracking` feature 时存在
+    #[cfg(feature = "debug_borrow_tracking")]
+    pub last_borrow: Option<String>,
+}
+
+impl fmt::Display for BorrowConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.held {
+            BorrowKind::Mutable => f.write_str("already mutably borrowed")?,
+            BorrowKind::Shared => f.write_str("already borrowed")?,
+        }
+        write!(f, " (gen {})", self.generation)?;
+        #[cfg(feature = "debug_borrow_tracking")]
+        match &self.last_borrow {
+            Some(bt) => write!(f, "; [debug_borrow_tracking] conflicting borrow taken at:\n{bt}")?,
+            None => f.write_str("; [debug_borrow_tracking] no borrow recorded")?,
+        }
+        Ok(())
+    }
+}
+
+impl Error for BorrowConflict {}
+
+impl<T> FlagRef<T> {
+    /// 空指针实例
+    // 抄的std::rc::Weak::new()方法。
+    pub const EMPTY: Self =
+        Self( InnerFlag(NonNull::without_provenance(NonZeroUsize::MAX)) );
+    
+    /// 生成一个不持有计数的 [`FlagHandle`]，记录当前地址与代数
+    ///
+    /// 空的 `FlagRef` 生成的句柄永远无法升级。
+    pub fn to_handle(&self) -> FlagHandle<T> {
+        let ptr = self.0.inner_ptr().as_ptr();
+        let generation = if is_dangling(ptr) { 0 } else { self.0.generation() };
+        FlagHandle {
+            addr: ptr.expose_provenance(),
+            generation,
+            _marker: PhantomData,
+        }
+    }
+    
+    /// 将 [`FlagHandle`] 升级为计数的 `FlagRef`
+    ///
+    /// 若句柄代数与当前代数不符（即期间发生过复活），或句柄来自空的 `FlagRef`，返回 `Empty`。
+    ///
+    /// # SAFETY
+    /// 句柄本身不保活内存。调用者必须保证句柄所指的核心结构仍未被释放，
+    /// 例如仍有其他 `FlagCell` 或 `FlagRef` 存活。
+    pub unsafe fn upgrade_handle(handle: FlagHandle<T>) -> FlagRefOption<FlagRef<T>> {
+        if handle.addr == usize::MAX {
+            return FlagRefOption::Empty;
+        }
+        let ptr = ptr::with_exposed_provenance_mut::<FlagHeader<T>>(handle.addr);
+        // SAFETY: 调用者保证内存有效，地址来自合法的 NonNull
+        let inner = InnerFlag(unsafe { NonNull::new_unchecked(ptr) });
+        // 仅剩 WeakFlagRef 时内部数据已析构
+        if inner.count_ref().get() == 0 || inner.generation() != handle.generation {
+            return FlagRefOption::Empty;
+        }
+        inner.inc_ref_count();
+        FlagRefOption::Some(FlagRef(inner))
+    }
+    
+    /// 由地址与代数得到一个 `u64` 键，可用作外部索引结构（如 slotmap、`HashMap`）的键
+    ///
+    /// 同一块内存复活后代数变化，键随之改变。需要由键取回 `FlagRef` 时，
+    /// 应将 [`to_handle`](#method.to_handle) 得到的句柄与键一同存储，再经 [`upgrade_handle`](#method.upgrade_handle) 校验。
+    /// 详见 [`FlagHandle::key`]。
+    pub fn key(&self) -> u64 {
+        self.to_handle().key()
+    }
+    
+    /// 创建一个不指向任何内容的 `FlagRef`
+    ///
+    /// 尝试调用任何方法都将返回 `Empty`
+    pub fn new() -> Self {
+        Self::EMPTY
+    }
+    
+    /// 不经由 `FlagCell`，直接创建一个指向 `value` 的 `FlagRef`
+    ///
+    /// 数据处于禁用状态（如同对应的 `FlagCell` 已被销毁），但会一直存活到最后一个 `FlagRef` 销毁。
+    /// 之后可通过 [`resurrect`](#method.resurrect) 得到第一个 `FlagCell`。
+    pub fn new_detached(value: T) -> Self {
+        let header = FlagHeader::new(value);
+        // 没有 FlagCell，唯一的持有者是这个 FlagRef，且处于禁用状态
+        header.count.set(-1);
+        Self(InnerFlag(NonNull::from(Box::leak(Box::new(header)))))
+    }
+}
+
+impl<T: ?Sized> FlagRef<T> {
+    pub fn ref_count(&self) -> isize {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),0);
+        // 减去可能存在的 FlagCell：不能以启用状态判断，FlagCell 存活时同样可能已禁用
+        let owned = isize::from(self.0.has_flag(FLAG_OWNED));
+        debug_assert!(self.0.ref_count() > owned);
+        self.0.ref_count() - owned
+    }
+    
+    /// 获取保活内部数据的持有者总数，即 `FlagCell`（若存在）、所有 `FlagRef` 与 [`CountLease`]
+    ///
+    /// 空的 `FlagRef` 返回0，详见 [`FlagCell::strong_count`]
+    pub fn strong_count(&self) -> isize {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),0);
+        self.0.ref_count()
+    }
+    
+    /// 获取核心结构中原始的计数值，空的 `FlagRef` 返回 `None`
+    ///
+    /// 详见 [`FlagCell::raw_count`]
+    pub fn raw_count(&self) -> Option<isize> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),None);
+        Some(self.0.count_ref().get())
+    }
+    
+    /// 自己是否为最后一个持有者，即 `FlagCell` 已销毁且不存在其他 `FlagRef` 或 [`CountLease`]
+    ///
+    /// 为 `true` 时销毁自己将析构内部数据（仍有 [`WeakFlagRef`] 时内存稍后释放）。
+    /// 空的 `FlagRef` 返回 `false`。
+    pub fn is_last(&self) -> bool {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),false);
+        self.0.ref_count() == 1 && !self.0.has_flag(FLAG_OWNED)
+    }
+    
+    /// 获取除自己以外的 `FlagRef` 数量，即 [`ref_count`](#method.ref_count) 减一，最小为0
+    ///
+    /// 空的 `FlagRef` 返回0
+    pub fn other_ref_count(&self) -> isize {
+        (self.ref_count() - 1).max(0)
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),false);
+        self.0.is_enabled()
+    }
+    
+    /// 获取当前代数，空的 `FlagRef` 返回 `None`
+    ///
+    /// 详见 [`FlagCell::generation`]
+    pub fn generation(&self) -> Option<u64> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),None);
+        Some(self.0.generation())
+    }
+    
+    /// 获取当前变更版本，空的 `FlagRef` 返回 `None`
+    ///
+    /// 详见 [`FlagCell::version`]
+    pub fn version(&self) -> Option<u64> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),None);
+        Some(self.0.version())
+    }
+    
+    /// 生成一行便于日志与测试使用的描述
+    ///
+    /// 格式为 `FlagRef@{地址} enabled={} refs={} gen={}`，其中 `refs` 同 [`ref_count`](#method.ref_count)；
+    /// 空的 `FlagRef` 为 `FlagRef@empty`
+    pub fn describe(&self) -> String {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(), String::from("FlagRef@empty"));
+        format!(
+            "FlagRef@{:#x} enabled={} refs={} gen={}",
+            self.0.addr(),
+            self.is_enabled(),
+            self.ref_count(),
+            self.0.generation()
+        )
+    }
+    
+    /// 获取用户标签，空的 `FlagRef` 返回 `None`
+    ///
+    /// 详见 [`FlagCell::tag`]
+    pub fn tag(&self) -> Option<u32> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),None);
+        Some(self.0.tag())
+    }
+    
+    /// 一次性获取引用数量、启用状态与代数，空的 `FlagRef` 返回 `None`
+    ///
+    /// 详见 [`FlagSnapshot`]
+    pub fn snapshot(&self) -> Option<FlagSnapshot> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),None);
+        Some(FlagSnapshot {
+            ref_count: self.ref_count(),
+            enabled: self.is_enabled(),
+            generation: self.0.generation(),
+        })
+    }
+    
+    /// 强制将数据逻辑启用
+    ///
+    /// # SAFETY
+    /// 本方法为**逻辑不安全操作**：无内存未定义行为、无 panic 风险。
+    /// 暴露此方法是为了满足特定场景的便捷性需求。
+    ///
+    /// 此方法会虚构出一个 `FlagCell` ，可能造成其他相关类型功能异常。
+    ///
+    /// 内部数据已被析构时同样会被启用，但借用仍返回 `DataDropped`。
+    pub unsafe fn enable(&self) -> FlagRefOption<()> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),FlagRefOption::Empty);
+        self.0.enable_logical();
+        FlagRefOption::Some(())
+    }
+    
+    /// 强制将数据逻辑禁用
+    ///
+    /// # SAFETY
+    /// 本方法为**逻辑不安全操作**：无内存未定义行为、无 panic 风险。
+    /// 暴露此方法是为了满足特定场景的便捷性需求。
+    ///
+    /// 此方法会强制 `RefCell` 失效，可能造成其他相关类型功能异常。
+    ///
+    /// 若当前存在 [`StrongLease`]，禁用失败，返回 `Conflict`
+    pub unsafe fn disable(&self) -> FlagRefOption<()> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),FlagRefOption::Empty);
+        if self.0.is_leased() {
+            return FlagRefOption::Conflict;
+        }
+        self.0.disable_logical();
+        FlagRefOption::Some(())
+    }
+    
+    /// 尝试借用内部值。
+    ///
+    /// 详见 [`FlagRefOption`]
+    pub fn try_borrow(&self) -> FlagRefOption<Ref<'_, T>> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(), FlagRefOption::Empty);
+        // 必须先于启用状态检查：数据已析构时计数仍可能被强制启用
+        if self.0.has_flag(FLAG_DATA_DROPPED) {
+            return FlagRefOption::DataDropped;
+        }
+        if !self.is_enabled() {
+            return FlagRefOption::Disabled;
+        }
+        if self.0.has_flag(FLAG_POISONED) {
+            return FlagRefOption::Conflict;
+        }
+        let borrow = unsafe { self.0.as_ref_unchecked().try_borrow().ok() };
+        if borrow.is_some() {
+            self.0.record_borrow();
+        }
+        // 解包ManuallyDrop<T> → T
+        let borrow_unwrapped = borrow.map(|r| Ref::map(r, |md| md.deref()));
+        FlagRefOption::from_borrow(borrow_unwrapped)
+    }
+    
+    /// 尝试可变借用内部值。
+    ///
+    /// 详见 [`FlagRefOption`]
+    pub fn try_borrow_mut(&self) -> FlagRefOption<RefMut<'_, T>> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(), FlagRefOption::Empty);
+        // 必须先于启用状态检查：数据已析构时计数仍可能被强制启用
+        if self.0.has_flag(FLAG_DATA_DROPPED) {
+            return FlagRefOption::DataDropped;
+        }
+        if !self.is_enabled() {
+            return FlagRefOption::Disabled;
+        }
+        if self.0.has_flag(FLAG_POISONED | FLAG_FROZEN) {
+            return FlagRefOption::Conflict;
+        }
+        let borrow = unsafe { self.0.as_ref_unchecked().try_borrow_mut().ok() };
+        if borrow.is_some() {
+            self.0.record_borrow();
+        }
+        // 解包ManuallyDrop<T> → T
+        let borrow_unwrapped = borrow.map(|r| RefMut::map(r, |md| md.deref_mut()));
+        FlagRefOption::from_borrow(borrow_unwrapped)
+    }
+    
+    /// 可变借用内部迭代器并取出下一项
+    ///
+    /// 迭代结束时返回 `Empty`，其余失败情况同 [`try_borrow_mut`](#method.try_borrow_mut)。
+    ///
+    /// 这不是 [`Iterator`] 的实现，详见 [`FlagCell::next`]。
+    pub fn next(&self) -> FlagRefOption<T::Item>
+    where
+        T: Iterator,
+    {
+        match self.try_borrow_mut() {
+            FlagRefOption::Some(mut it) => it.next().map_or(FlagRefOption::Empty, FlagRefOption::Some),
+            other => other.map(|_| unreachable!()),
+        }
+    }
+    
+    /// 生成一个投影到内部某一部分（例如字段）的 [`FlagProj`]
+    ///
+    /// 投影持有一个新的 `FlagRef`，因此同样保活内存，并跟随父级的启用状态。
+    pub fn project<U: ?Sized>(&self, f: fn(&T) -> &U) -> FlagProj<T, U> {
+        FlagProj {
+            parent: self.clone(),
+            project: f,
+        }
+    }
+    
+    /// 借用内部值并对其应用 `f`，随即释放借用，返回 `f` 的结果
+    ///
+    /// 返回值不与借用守卫关联，可自由持有。失败情况同 [`try_borrow`](#method.try_borrow)。
+    pub fn map_cloned<U>(&self, f: impl FnOnce(&T) -> U) -> FlagRefOption<U> {
+        self.try_borrow().map(|r| f(&r))
+    }
+    
+    /// 比较 `self` 与 `other` 所指向的内部值是否相等，同 [`value_eq`](#method.value_eq)
+    ///
+    /// `FlagRef` 刻意不实现 [`PartialEq`]，以此方法显式进行值比较。
+    pub fn try_value_eq(&self, other: &FlagRef<T>) -> FlagRefOption<bool>
+    where
+        T: PartialEq,
+    {
+        self.value_eq(other)
+    }
+    
+    /// 比较 `self` 与 `other` 所指向的内部值是否相等
+    ///
+    /// 两者均可借用时返回 `Some(bool)`，否则返回第一个失败的变体（优先 `self`），详见 [`FlagRefOption::zip`]。
+    /// 两者指向同一目标时只做共享借用，不会冲突。
+    pub fn value_eq(&self, other: &FlagRef<T>) -> FlagRefOption<bool>
+    where
+        T: PartialEq,
+    {
+        self.try_borrow().zip(other.try_borrow()).map(|(a, b)| *a == *b)
+    }
+    
+    /// 尝试借用内部值，失败时返回详细原因。
+    ///
+    /// 详见 [`FlagBorrowError`]
+    pub fn try_borrow_detailed(&self) -> Result<Ref<'_, T>, FlagBorrowError> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(), Err(FlagBorrowError::Empty));
+        if self.0.has_flag(FLAG_DATA_DROPPED) {
+            return Err(FlagBorrowError::DataDropped);
+        }
+        if !self.is_enabled() {
+            return Err(FlagBorrowError::Disabled);
+        }
+        if self.0.has_flag(FLAG_POISONED) {
+            return Err(FlagBorrowError::Poisoned);
+        }
+        let borrow = unsafe { self.0.as_ref_unchecked().try_borrow()? };
+        self.0.record_borrow();
+        // 解包ManuallyDrop<T> → T
+        Ok(Ref::map(borrow, |md| md.deref()))
+    }
+    
+    /// 尝试可变借用内部值，失败时返回详细原因。
+    ///
+    /// 详见 [`FlagBorrowError`]
+    pub fn try_borrow_mut_detailed(&self) -> Result<RefMut<'_, T>, FlagBorrowError> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(), Err(FlagBorrowError::Empty));
+        if self.0.has_flag(FLAG_DATA_DROPPED) {
+            return Err(FlagBorrowError::DataDropped);
+        }
+        if !self.is_enabled() {
+            return Err(FlagBorrowError::Disabled);
+        }
+        if self.0.has_flag(FLAG_POISONED) {
+            return Err(FlagBorrowError::Poisoned);
+        }
+        if self.0.has_flag(FLAG_FROZEN) {
+            return Err(FlagBorrowError::Frozen);
+        }
+        let borrow = unsafe { self.0.as_ref_unchecked().try_borrow_mut()? };
+        self.0.record_borrow();
+        // 解包ManuallyDrop<T> → T
+        Ok(RefMut::map(borrow, |md| md.deref_mut()))
+    }
+    
+    /// 尝试复活 `FlagCell`
+    ///
+    /// 仅当前对应 `FlagCell` 销毁即数据逻辑禁用时，可复活，否则返回 `Disabled` 。
+    /// 若 `FlagCell` 仍存活、仅是被禁用，返回 `Conflict`，以免同时存在两个 `FlagCell`。
+    pub fn resurrect(&self) -> FlagRefOption<FlagCell<T>> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),FlagRefOption::Empty);
+        if self.0.has_flag(FLAG_DATA_DROPPED) {
+            return FlagRefOption::DataDropped;
+        }
+        if self.is_enabled() {
+            return FlagRefOption::Disabled;
+        }
+        if self.0.has_flag(FLAG_OWNED) {
+            return FlagRefOption::Conflict;
+        }
+        // 复活即新的逻辑对象，嵌套禁用深度一并清零
+        self.0.enable();
+        self.0.set_depth(0);
+        self.0.inc_ref_count();
+        self.0.bump_generation();
+        FlagRefOption::Some(FlagCell::from_inner(self.0.inner_ptr()))
+    }
+    
+    /// 消费自身，若为唯一持有者则转换为唯一的 `FlagCell` 并启用
+    ///
+    /// 与 [`resurrect`](#method.resurrect) 一样视为新的逻辑对象：代数加一，嵌套禁用深度清零。
+    ///
+    /// 空的 `FlagRef` 返回 `Empty`；还存在其他持有者（`FlagCell`、`FlagRef` 或 [`CountLease`]）时返回 `Conflict`。
+    /// 注意失败时 `self` 同样被消费（正常 drop），不会返还。
+    pub fn into_owner(self) -> FlagRefOption<FlagCell<T>> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),FlagRefOption::Empty);
+        if self.0.has_flag(FLAG_DATA_DROPPED) {
+            return FlagRefOption::DataDropped;
+        }
+        if self.0.ref_count() != 1 {
+            return FlagRefOption::Conflict;
+        }
+        debug_assert!(!self.0.has_flag(FLAG_OWNED));
+        // 唯一的计数直接转交给新的 FlagCell
+        self.0.enable();
+        self.0.set_depth(0);
+        self.0.bump_generation();
+        let cell = FlagCell::from_inner(self.0.inner_ptr());
+        mem::forget(self);
+        FlagRefOption::Some(cell)
+    }
+    
+    /// 借用内部值，若数据已禁用则先尝试复活再借用
+    ///
+    /// - 数据启用时，等同于 [`try_borrow`](#method.try_borrow)，元组第一项为 `None`；
+    /// - 数据禁用时，复活出新的 `FlagCell` 并借用，元组第一项为 `Some`。
+    ///
+    /// 返回的 `Ref` 借用自 `self` 而非新的 `FlagCell`：即使先 drop 掉返回的 `FlagCell`
+    /// （数据随之再次禁用），`Ref` 依然有效，因为 `self` 保证内存不会被释放。
+    /// 若需要数据保持启用，应当让返回的 `FlagCell` 活得比 `Ref` 更久。
+    ///
+    /// 若数据禁用且正被可变借用或已中毒，返回 `Conflict`，此时不会复活。
+    pub fn borrow_or_resurrect(&self) -> FlagRefOption<(Option<FlagCell<T>>, Ref<'_, T>)> {
+        match self.try_borrow() {
+            FlagRefOption::Some(r) => FlagRefOption::Some((None, r)),
+            FlagRefOption::Disabled => {
+                // try_borrow 在禁用时不检查中毒，此处补上，不能交出中毒的数据
+                if self.0.has_flag(FLAG_POISONED) {
+                    return FlagRefOption::Conflict;
+                }
+                // 先借用再复活，避免复活后才发现借用冲突
+                let borrow = match unsafe { self.0.as_ref_unchecked().try_borrow() } {
+                    Ok(r) => Ref::map(r, |md| md.deref()),
+                    Err(_) => return FlagRefOption::Conflict,
+                };
+                self.resurrect().map(|cell| (Some(cell), borrow))
+            }
+            other => other.map(|_| unreachable!()),
+        }
+    }
+    
+    /// 借用内部值，若数据已禁用则先强制启用再重试一次
+    ///
+    /// 与 [`borrow_or_resurrect`](#method.borrow_or_resurrect) 不同，不会创建新的 `FlagCell`，
+    /// 数据在借用结束后保持启用。嵌套模式下每次调用只使禁用深度减一，深度未归零时仍返回 `Disabled`。
+    ///
+    /// 内部数据已被析构时返回 `DataDropped`，不会启用。
+    ///
+    /// # SAFETY
+    /// 同 [`enable`](#method.enable)：本方法为**逻辑不安全操作**，会越过 `FlagCell` 的禁用决定。
+    pub unsafe fn borrow_enabling(&self) -> FlagRefOption<Ref<'_, T>> {
+        match self.try_borrow() {
+            FlagRefOption::Disabled => {
+                // SAFETY: 由调用者保证
+                unsafe { self.enable() };
+                self.try_borrow()
+            }
+            other => other,
+        }
+    }
+    
+    /// 获取一个强租约，租约存活期间数据无法被禁用
+    ///
+    /// 租约存活期间，[`FlagCell::disable`] 返回 `None`，[`FlagRef::disable`] 返回 `Conflict`。
+    /// 租约在 drop 时自动释放。
+    ///
+    /// 注意：`FlagCell` 被销毁时无视租约，数据仍会被禁用。
+    ///
+    /// 若当前数据已禁用，返回 `Disabled`。
+    pub fn upgrade_strong(&self) -> FlagRefOption<StrongLease<'_, T>> {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),FlagRefOption::Empty);
+        if !self.is_enabled() {
+            return FlagRefOption::Disabled;
+        }
+        self.0.set_leases(self.0.leases() + 1);
+        FlagRefOption::Some(StrongLease(self))
+    }
+}
 
-/// 从FlagCell产生的轻量共享引用，可Clone，单线程使用
+impl FlagRef<dyn Any> {
+    /// 尝试转换回具体类型的 `FlagRef<T>`，计数直接转交
+    ///
+    /// 类型不符时返还 `Err(self)`。判断类型需要短暂地共享借用内部值，
+    /// 因此内部值正被可变借用时同样返还 `Err(self)`。
+    pub fn downcast<T: Any>(self) -> Result<FlagRef<T>, Self> {
+        if self.0.has_flag(FLAG_DATA_DROPPED) {
+            return Err(self);
+        }
+        // SAFETY: 数据未被取走时 FlagRef 存活即内部数据存活；仅在未被可变借用时取得共享引用
+        let is_t = match unsafe { self.0.as_ref_unchecked().try_borrow_unguarded() } {
+            Ok(md) => <dyn Any>::is::<T>(&**md),
+            Err(_) => false,
+        };
+        if !is_t {
+            return Err(self);
+        }
+        let ptr = self.0.inner_ptr().cast::<FlagHeader<T>>();
+        mem::forget(self);
+        Ok(FlagRef(InnerFlag(ptr)))
+    }
+}
+
+// 与 Rc 同名的别名，详见 FlagCell 文档中的术语对照
+impl<T: ?Sized> FlagCell<T> {
+    /// 同 [`flag_borrow`](#method.flag_borrow)，对标 [`Rc::downgrade`](std::rc::Rc::downgrade)
+    pub fn downgrade(&self) -> FlagRef<T> {
+        self.flag_borrow()
+    }
+    
+    /// 获取 [`WeakFlagRef`] 数量，对标 [`Rc::weak_count`](std::rc::Rc::weak_count)
+    pub fn weak_count(&self) -> usize {
+        // 去掉强持有者共同持有的隐式计数
+        self.0.weak_ref().get() - 1
+    }
+}
+
+impl<T: ?Sized> FlagRef<T> {
+    /// 同 [`resurrect`](#method.resurrect)，对标 [`Weak::upgrade`](std::rc::Weak::upgrade)
+    pub fn upgrade(&self) -> FlagRefOption<FlagCell<T>> {
+        self.resurrect()
+    }
+    
+    /// 获取 [`WeakFlagRef`] 数量，空的 `FlagRef` 返回0，对标 [`Weak::weak_count`](std::rc::Weak::weak_count)
+    pub fn weak_count(&self) -> usize {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(),0);
+        // 去掉强持有者共同持有的隐式计数
+        self.0.weak_ref().get() - 1
+    }
+}
+
+impl<T> Default for FlagRef<T>{
+    /// 创建一个不指向任何内容的 `FlagRef`
+    ///
+    /// 尝试调用任何方法都将返回 `Empty`
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> Drop for FlagRef<T> {
+    // 与FlagCell的drop严格互斥
+    fn drop(&mut self) {
+        let ptr = self.0.inner_ptr();
+        dangling_then_return!(ptr.as_ptr());
+        
+        let new_count = self.0.dec_ref_count();
+        if new_count == 0 {
+            // SAFETY: 计数0=Cell不存在=无其他引用，指针合法。
+            // new_count 首次归零意味着，内存未曾释放，这是唯一释放点。
+            unsafe { release_header(ptr) }
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for FlagRef<T> {
+    /// 克隆一个 FlagRef，使引用计数加一
+    ///
+    /// 空的 `FlagRef` 克隆后仍为空
+    fn clone(&self) -> Self {
+        dangling_then_return!(self.0.inner_ptr().as_ptr(), Self(InnerFlag(self.0.inner_ptr())));
+        self.0.inc_ref_count();
+        Self(InnerFlag(self.0.inner_ptr()))
+    }
+}
+
+impl<T: ?Sized> fmt::Pointer for FlagRef<T> {
+    /// 输出核心结构地址，用于身份调试
+    ///
+    /// 空的 `FlagRef` 输出哨兵地址 `usize::MAX`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.0.inner_ptr(), f)
+    }
+}
+
+// impl<T> !Send for FlagRef<T> {}
+// impl<T> !Sync for FlagRef<T> {}
+
+/// 由 [`FlagCell::lease`] 产生的保活租约，drop 时释放计数
+///
+/// 只保证内部数据存活，不提供访问，也不影响启用状态。
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct CountLease<T: ?Sized>(InnerFlag<T>);
+
+impl<T: ?Sized> Drop for CountLease<T> {
+    fn drop(&mut self) {
+        let new_count = self.0.dec_ref_count();
+        if new_count == 0 {
+            // SAFETY: 同 FlagRef 的 drop
+            unsafe { release_header(self.0.inner_ptr()) }
+        }
+    }
+}
+
+/// 由 [`FlagCell::clone_ref_weak`] 产生的弱引用，不保活内部数据，只保活核心结构的内存
+///
+/// 所有 `FlagCell` 与 [`FlagRef`] 销毁后内部数据随即析构，此后无法升级。
 #[repr(transparent)]
 #[derive(Debug)]
-pub struct FlagRef<T>(InnerFlag<T>);
+pub struct WeakFlagRef<T: ?Sized>(InnerFlag<T>);
+
+impl<T: ?Sized> WeakFlagRef<T> {
+    /// 升级为保活内部数据的 [`FlagRef`]
+    ///
+    /// 所有持有者均已销毁、内部数据随之析构时返回 `Empty`；只要仍有持有者就可升级：
+    /// - 数据仅被禁用时，得到的 `FlagRef` 借用时返回 `Disabled`；
+    /// - 数据已被 [`FlagCell::force_unwrap`] 取走或被 [`FlagCell::drop_payload`] 析构时，同样返回 `Some`，
+    ///   得到的 `FlagRef` 借用时返回 `DataDropped`。
+    pub fn upgrade(&self) -> FlagRefOption<FlagRef<T>> {
+        if self.0.count_ref().get() == 0 {
+            return FlagRefOption::Empty;
+        }
+        self.0.inc_ref_count();
+        FlagRefOption::Some(FlagRef(InnerFlag(self.0.inner_ptr())))
+    }
+    
+    /// 是否仍有持有者，即 [`upgrade`](#method.upgrade) 能否成功
+    ///
+    /// 数据被取走或析构但仍有持有者时同样返回 `true`。
+    pub fn is_alive(&self) -> bool {
+        self.0.count_ref().get() != 0
+    }
+}
+
+impl<T: ?Sized> Clone for WeakFlagRef<T> {
+    fn clone(&self) -> Self {
+        let weak = self.0.weak_ref();
+        weak.set(weak.get() + 1);
+        Self(InnerFlag(self.0.inner_ptr()))
+    }
+}
+
+impl<T: ?Sized> Drop for WeakFlagRef<T> {
+    fn drop(&mut self) {
+        // SAFETY: 弱计数归零前内存有效；仅当内部数据已析构时隐式计数已释放，弱计数才可能归零
+        unsafe { release_weak(self.0.inner_ptr()) }
+    }
+}
+
+/// 由 [`FlagRef::to_handle`] 产生的可复制句柄，记录 (地址, 代数)
+///
+/// 句柄不持有计数，不保活内存，适用于 ECS 等需要存储大量轻量句柄的场景。
+/// 通过 [`FlagRef::upgrade_handle`] 校验代数后得到计数的 `FlagRef`。
+pub struct FlagHandle<T> {
+    addr: usize,
+    generation: u64,
+    _marker: PhantomData<*const T>,
+}
+
+impl<T> FlagHandle<T> {
+    /// 句柄记录的核心结构地址
+    pub fn addr(&self) -> usize {
+        self.addr
+    }
+    
+    /// 句柄记录的代数
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+    
+    /// 将地址与代数打包为一个 `u64` 键
+    ///
+    /// 代数循环左移 48 位后与地址异或：同一地址的不同代数必然得到不同的键；
+    /// 在地址不超过 48 位且代数小于 2<sup>16</sup> 时，不同地址的键也互不相同。
+    pub fn key(&self) -> u64 {
+        (self.addr as u64) ^ self.generation.rotate_left(48)
+    }
+    
+    /// 是否与 `cell` 为同一逻辑对象，即地址相同且代数与句柄生成时相同
+    ///
+    /// 同一块内存经复活成为新的逻辑对象后返回 `false`。缓存 `FlagRef` 时一并缓存
+    /// [`FlagRef::to_handle`] 得到的句柄，即可在之后判断期间是否发生过复活。
+    pub fn same_logical_object(&self, cell: &FlagCell<T>) -> bool {
+        self.addr == cell.0.addr() && self.generation == cell.generation()
+    }
+}
+
+impl<T> Clone for FlagHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FlagHandle<T> {}
+
+impl<T> PartialEq for FlagHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.addr == other.addr && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for FlagHandle<T> {}
+
+impl<T> Hash for FlagHandle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.addr.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for FlagHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlagHandle")
+            .field("addr", &self.addr)
+            .field("generation", &self.generation)
+            .finish()
+    }
+}
+
+/// 由 [`FlagRef::upgrade_strong`] 产生的强租约
+///
+/// 存活期间阻止数据被禁用，drop 时自动释放。可通过 Deref 直接使用对应的 [`FlagRef`]。
+#[derive(Debug)]
+pub struct StrongLease<'a, T: ?Sized>(&'a FlagRef<T>);
+
+impl<T: ?Sized> Deref for StrongLease<'_, T> {
+    type Target = FlagRef<T>;
+    
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Drop for StrongLease<'_, T> {
+    fn drop(&mut self) {
+        // 租约存在即 FlagRef 存在，内存必然有效
+        let inner = &(self.0).0;
+        debug_assert!(inner.leases() > 0);
+        inner.set_leases(inner.leases() - 1);
+    }
+}
+
+/// 由 [`FlagRef::project`] 产生的投影句柄，如同只能看到某一部分的 `FlagRef`
+///
+/// 每次借用都在父级借用内应用投影函数，因此父级禁用后投影同样返回 `Disabled`。
+pub struct FlagProj<T: ?Sized, U: ?Sized> {
+    parent: FlagRef<T>,
+    project: fn(&T) -> &U,
+}
+
+impl<T: ?Sized, U: ?Sized> FlagProj<T, U> {
+    /// 获取父级 [`FlagRef`]
+    pub fn parent(&self) -> &FlagRef<T> {
+        &self.parent
+    }
+    
+    /// 父级数据是否逻辑启用
+    pub fn is_enabled(&self) -> bool {
+        self.parent.is_enabled()
+    }
+    
+    /// 尝试借用投影后的值
+    ///
+    /// 详见 [`FlagRef::try_borrow`]
+    pub fn try_borrow(&self) -> FlagRefOption<Ref<'_, U>> {
+        self.parent.try_borrow().map(|r| Ref::map(r, self.project))
+    }
+    
+    /// 尝试借用投影后的值，失败时返回详细原因
+    ///
+    /// 详见 [`FlagRef::try_borrow_detailed`]
+    pub fn try_borrow_detailed(&self) -> Result<Ref<'_, U>, FlagBorrowError> {
+        self.parent.try_borrow_detailed().map(|r| Ref::map(r, self.project))
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> Clone for FlagProj<T, U> {
+    fn clone(&self) -> Self {
+        Self {
+            parent: self.parent.clone(),
+            project: self.project,
+        }
+    }
+}
+
+impl<T: ?Sized, U: ?Sized> fmt::Debug for FlagProj<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FlagProj")
+            .field("parent", &self.parent.describe())
+            .finish_non_exhaustive()
+    }
+}
+
+/// 由 [`FlagCell::borrow_mut_tracked`] 产生的可变借用守卫，drop 时变更版本加一
+#[derive(Debug)]
+pub struct TrackedRefMut<'a, T: ?Sized> {
+    inner: RefMut<'a, T>,
+    flag: &'a InnerFlag<T>,
+}
+
+impl<T: ?Sized> Deref for TrackedRefMut<'_, T> {
+    type Target = T;
+    
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ?Sized> DerefMut for TrackedRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: ?Sized> Drop for TrackedRefMut<'_, T> {
+    fn drop(&mut self) {
+        self.flag.bump_version();
+    }
+}
+
+/// 由 [`FlagCell::borrow_mut_guarded`] 产生的可变借用守卫，中毒模式下于 panic 展开期间 drop 时使数据中毒
+#[derive(Debug)]
+pub struct PoisonRefMut<'a, T: ?Sized> {
+    inner: RefMut<'a, T>,
+    flag: &'a InnerFlag<T>,
+}
+
+impl<T: ?Sized> Deref for PoisonRefMut<'_, T> {
+    type Target = T;
+    
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: ?Sized> DerefMut for PoisonRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: ?Sized> Drop for PoisonRefMut<'_, T> {
+    fn drop(&mut self) {
+        if thread::panicking() && self.flag.has_flag(FLAG_POISONING) {
+            self.flag.set_flag(FLAG_POISONED);
+        }
+    }
+}
 
-/// Some: 可借用 <br>
-/// Conflict: 借用冲突，不符合rust借用原则
-/// Empty: 内部为空，即此FlagRef是从new函数创建的
-/// Disabled: 内部数据当前已禁用
+/// 由 [`FlagCell::borrow_cow`] 产生的写时复制借用
+///
+/// 类似 [`std::borrow::Cow`]，但 `Borrowed` 持有的是 `Ref` 守卫。
 #[derive(Debug)]
-pub enum FlagRefOption<T> {
-    Some(T),
-    Conflict,
-    Empty,
-    Disabled,
+pub enum FlagCow<'a, T> {
+    Borrowed(Ref<'a, T>),
+    Owned(T),
 }
 
-impl<T> FlagRefOption<T> {
-    /// 解包 FlagRefOption
-    ///
-    /// # Panics
-    /// 若非 `Some` ，panic
-    pub fn unwrap(self) -> T {
-        if let FlagRefOption::Some(val) = self {
-            val
+impl<T: Clone> FlagCow<'_, T> {
+    /// 得到内部值的所有权，若为 `Borrowed` 则克隆
+    pub fn into_owned(self) -> T {
+        match self {
+            FlagCow::Borrowed(r) => T::clone(&r),
+            FlagCow::Owned(v) => v,
         }
-        else {
-            panic!("called `FlagRefOption::unwrap()` on a not `Some` value")
+    }
+    
+    /// 得到可变引用，若为 `Borrowed` 则先克隆为 `Owned`，并释放借用
+    pub fn to_mut(&mut self) -> &mut T {
+        if let FlagCow::Borrowed(r) = self {
+            *self = FlagCow::Owned(T::clone(r));
+        }
+        match self {
+            FlagCow::Owned(v) => v,
+            FlagCow::Borrowed(_) => unreachable!(),
         }
     }
+}
+
+impl<T> FlagCow<'_, T> {
+    /// 是否为 `Borrowed`
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, FlagCow::Borrowed(_))
+    }
+}
+
+impl<T> Deref for FlagCow<'_, T> {
+    type Target = T;
     
-    /// 将自己转换为原生 `Option` 类型
+    fn deref(&self) -> &T {
+        match self {
+            FlagCow::Borrowed(r) => r,
+            FlagCow::Owned(v) => v,
+        }
+    }
+}
+
+/// 由 [`FlagCell::borrow_mut_or_clone`] 产生的可变借用：要么是 `RefMut` 守卫，要么是脱离原值的克隆
+#[derive(Debug)]
+pub struct CowMut<'a, T> {
+    cell: &'a FlagCell<T>,
+    state: CowMutState<'a, T>,
+}
+
+#[derive(Debug)]
+enum CowMutState<'a, T> {
+    Borrowed(RefMut<'a, T>),
+    Owned(T),
+}
+
+impl<T> CowMut<'_, T> {
+    /// 是否直接借用了内部值
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.state, CowMutState::Borrowed(_))
+    }
+    
+    /// 结束本次修改
     ///
-    /// Some转换为Some，其余全部转换为None
-    pub fn into_option(self) -> Option<T> {
-        self.into()
+    /// 直接借用时只释放守卫；持有克隆时尝试将其写回原 `FlagCell`，
+    /// 冲突尚未解除（或无法可变借用）时原样返还 `Err(self)`，可稍后重试。
+    pub fn commit(self) -> Result<(), Self> {
+        let CowMut { cell, state } = self;
+        match state {
+            CowMutState::Borrowed(_) => Ok(()),
+            CowMutState::Owned(value) => match cell.try_borrow_mut() {
+                Some(mut r) => {
+                    *r = value;
+                    Ok(())
+                }
+                None => Err(CowMut { cell, state: CowMutState::Owned(value) }),
+            },
+        }
     }
+}
+
+impl<T> Deref for CowMut<'_, T> {
+    type Target = T;
     
-    /// Maps an `FlagRefOption<T>` to `FlagRefOption<U>` by applying a function to a contained value (为`Some`) or returns 原变体 (非`Some`).
-    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> FlagRefOption<U> {
-        match self{
-            FlagRefOption::Some(v) => FlagRefOption::Some(f(v)),
-            FlagRefOption::Conflict => FlagRefOption::Conflict,
-            FlagRefOption::Empty => FlagRefOption::Empty,
-            FlagRefOption::Disabled => FlagRefOption::Disabled,
+    fn deref(&self) -> &T {
+        match &self.state {
+            CowMutState::Borrowed(r) => r,
+            CowMutState::Owned(v) => v,
         }
     }
 }
 
-impl<T> From<FlagRefOption<T>> for Option<T> {
-    fn from(f: FlagRefOption<T>) -> Option<T> {
-        match f {
-            FlagRefOption::Some(v) => Some(v),
-            _ => None,
+impl<T> DerefMut for CowMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        match &mut self.state {
+            CowMutState::Borrowed(r) => r,
+            CowMutState::Owned(v) => v,
         }
     }
 }
 
-impl<T> FlagRefOption<T> {
-    fn from_borrow(opt: Option<T>) -> Self {
-        opt.map(Self::Some).unwrap_or(Self::Conflict)
+/// 由 [`FlagRefOption::unwrap_or_static`] 产生的借用：要么是 `Ref` 守卫，要么是静态的默认值
+#[derive(Debug)]
+pub enum BorrowedOrStatic<'a, T: ?Sized + 'static> {
+    Borrowed(Ref<'a, T>),
+    Static(&'static T),
+}
+
+impl<T: ?Sized> BorrowedOrStatic<'_, T> {
+    /// 是否为 `Borrowed`
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, BorrowedOrStatic::Borrowed(_))
     }
 }
 
-impl<T> FlagRef<T> {
-    /// 空指针实例
-    // 抄的std::rc::Weak::new()方法。
-    pub const EMPTY: Self =
-        Self( InnerFlag(NonNull::without_provenance(NonZeroUsize::MAX)) );
+impl<T: ?Sized> Deref for BorrowedOrStatic<'_, T> {
+    type Target = T;
     
-    pub fn ref_count(&self) -> isize {
-        dangling_then_return!(self.0.inner_ptr().as_ptr(),0);
-        // 减去可能存在的 FlagCell
-        if self.is_enabled() { self.0.ref_count() - 1 } else { self.0.ref_count() }
+    fn deref(&self) -> &T {
+        match self {
+            BorrowedOrStatic::Borrowed(r) => r,
+            BorrowedOrStatic::Static(v) => v,
+        }
+    }
+}
+
+/// 强制实现 [`Send`] 的包装，用于在已证明无别名的前提下将本模块的类型移动到其他线程
+///
+/// 这**不会**让被包装的类型变得线程安全，只是一个显式的逃生舱。
+///
+/// # SAFETY
+/// 本模块的所有类型均为单线程设计，计数与借用状态都不是原子的。
+/// 使用者必须保证：从移动发生起，直到该值被移回原线程或销毁，
+/// 任何线程上都不存在能够触及同一核心结构的其他 `FlagCell`、`FlagRef` 或借用守卫。
+/// 违反此约定将导致数据竞争，即未定义行为。
+///
+/// 未经包装的 `FlagRef` 不是 `Send`：
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<flag_cell::FlagRef<u8>>();
+/// ```
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct ForceSend<T>(T);
+
+impl<T> ForceSend<T> {
+    /// 包装一个值，使其可以跨线程移动
+    ///
+    /// # SAFETY
+    /// 见 [`ForceSend`]
+    pub unsafe fn new(value: T) -> Self {
+        Self(value)
     }
     
-    pub fn is_enabled(&self) -> bool {
-        dangling_then_return!(self.0.inner_ptr().as_ptr(),false);
-        self.0.is_enabled()
+    /// 取出内部值
+    pub fn into_inner(self) -> T {
+        self.0
     }
+}
+
+impl<T> Deref for ForceSend<T> {
+    type Target = T;
     
-    /// 强制将数据逻辑启用
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ForceSend<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+// SAFETY: 由 ForceSend::new 的调用者保证
+unsafe impl<T> Send for ForceSend<T> {}
+
+/// 强制实现 [`Sync`] 的包装，用于在已证明无并发访问的前提下跨线程共享本模块的类型
+///
+/// 这**不会**让被包装的类型变得线程安全，只是一个显式的逃生舱。
+///
+/// # SAFETY
+/// 同 [`ForceSend`]，且使用者必须保证同一时刻至多一个线程经由共享引用访问内部值。
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<flag_cell::FlagRef<u8>>();
+/// ```
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct ForceSync<T>(T);
+
+impl<T> ForceSync<T> {
+    /// 包装一个值，使其可以跨线程共享
     ///
     /// # SAFETY
-    /// 本方法为**逻辑不安全操作**：无内存未定义行为、无 panic 风险。
-    /// 暴露此方法是为了满足特定场景的便捷性需求。
-    ///
-    /// 此方法会虚构出一个 `FlagCell` ，可能造成其他相关类型功能异常。
-    pub unsafe fn enable(&self) -> FlagRefOption<()> {
-        dangling_then_return!(self.0.inner_ptr().as_ptr(),FlagRefOption::Empty);
-        self.0.enable();
-        FlagRefOption::Some(())
+    /// 见 [`ForceSync`]
+    pub unsafe fn new(value: T) -> Self {
+        Self(value)
     }
     
-    /// 强制将数据逻辑禁用
-    ///
-    /// # SAFETY
-    /// 本方法为**逻辑不安全操作**：无内存未定义行为、无 panic 风险。
-    /// 暴露此方法是为了满足特定场景的便捷性需求。
-    ///
-    /// 此方法会强制 `RefCell` 失效，可能造成其他相关类型功能异常。
-    pub unsafe fn disable(&self) -> FlagRefOption<()> {
-        dangling_then_return!(self.0.inner_ptr().as_ptr(),FlagRefOption::Empty);
-        self.0.disable();
-        FlagRefOption::Some(())
+    /// 取出内部值
+    pub fn into_inner(self) -> T {
+        self.0
     }
+}
+
+impl<T> Deref for ForceSync<T> {
+    type Target = T;
     
-    /// 尝试借用内部值。
-    ///
-    /// 详见 [`FlagRefOption`]
-    pub fn try_borrow(&self) -> FlagRefOption<Ref<'_, T>> {
-        dangling_then_return!(self.0.inner_ptr().as_ptr(), FlagRefOption::Empty);
-        if !self.is_enabled() {
-            return FlagRefOption::Disabled;
-        }
-        let borrow = unsafe { self.0.as_ref_unchecked().try_borrow().ok() };
-        // 解包ManuallyDrop<T> → T
-        let borrow_unwrapped = borrow.map(|r| Ref::map(r, |md| md.deref()));
-        FlagRefOption::from_borrow(borrow_unwrapped)
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ForceSync<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+// SAFETY: 由 ForceSync::new 的调用者保证
+unsafe impl<T> Sync for ForceSync<T> {}
+
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    const fn assert_sync<T: Sync>() {}
+    assert_send::<ForceSend<FlagRef<u8>>>();
+    assert_sync::<ForceSync<FlagRef<u8>>>();
+};
+
+/// 移除 `v` 中所有目标不再可用的 [`FlagRef`]，并压缩 `v`
+///
+/// 空的 `FlagRef` 与目标已禁用的 `FlagRef` 都会被移除（即保留 [`FlagRef::is_enabled`] 为真者）。
+/// 被移除的 `FlagRef` 会正常 drop，若其为最后一个引用，内部数据随之释放。
+pub fn retain_live<T: ?Sized>(v: &mut Vec<FlagRef<T>>) {
+    v.retain(|r| r.is_enabled());
+}
+
+/// 同时可变借用两个 `FlagCell` 并执行 `f`
+///
+/// 借用顺序为先 `a` 后 `b`，`f` 返回后两者一同释放。
+///
+/// # Panics
+///
+/// Panics if either value is currently borrowed, including when `a` and `b` are the same cell.
+///
+#[track_caller]
+pub fn with_two_mut<T: ?Sized, U: ?Sized, R>(
+    a: &FlagCell<T>,
+    b: &FlagCell<U>,
+    f: impl FnOnce(&mut T, &mut U) -> R,
+) -> R {
+    let mut a = a.borrow_mut();
+    let mut b = b.borrow_mut();
+    f(&mut a, &mut b)
+}
+
+thread_local! {
+    /// 当前线程上嵌套的 [`catch_borrow_conflict`] 层数
+    static CATCHING_CONFLICTS: Cell<usize> = const { Cell::new(0) };
+    /// 正在展开的借用冲突，仅在 [`catch_borrow_conflict`] 内部记录
+    static PENDING_CONFLICT: Cell<Option<BorrowConflict>> = const { Cell::new(None) };
+}
+
+/// 因借用冲突 panic
+///
+/// panic 信息为冲突的文本，默认的 panic hook 可正常打印；
+/// 处于 [`catch_borrow_conflict`] 之内时，另将结构化的冲突记录在线程局部变量中，供其取回。
+#[cold]
+#[track_caller]
+fn raise_borrow_conflict(conflict: BorrowConflict) -> ! {
+    let message = conflict.to_string();
+    if CATCHING_CONFLICTS.with(Cell::get) > 0 {
+        PENDING_CONFLICT.with(|pending| pending.set(Some(conflict)));
+    }
+    panic!("{message}")
+}
+
+/// 执行 `f`，将其中因 [`BorrowConflict`] 引发的 panic 转换为 `Err`，其余 panic 照常传播
+///
+/// 冲突的 panic 发生在取得借用之前，发生冲突的那次借用不会留下任何修改；
+/// 但 `f` 在此之前已做的修改不会回滚，调用者需自行保证这些修改可被安全地放弃。
+/// 默认的 panic hook 仍会在捕获前打印一次 panic 信息。
+pub fn catch_borrow_conflict<R>(f: impl FnOnce() -> R) -> Result<R, BorrowConflict> {
+    CATCHING_CONFLICTS.with(|depth| depth.set(depth.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING_CONFLICTS.with(|depth| depth.set(depth.get() - 1));
+    // 取出记录的冲突：无论是否由本次 panic 产生，都不能遗留给外层
+    let pending = PENDING_CONFLICT.with(Cell::take);
+    match result {
+        Ok(r) => Ok(r),
+        Err(payload) => match pending {
+            // 只有载荷正是该冲突的文本时才认定为借用冲突，其间被 f 自行捕获的冲突不算
+            Some(conflict) if payload.downcast_ref::<String>() == Some(&conflict.to_string()) => Err(conflict),
+            _ => panic::resume_unwind(payload),
+        },
+    }
+}
+
+/// 以分配身份（核心结构地址）去重的 [`FlagRef`] 集合
+///
+/// 指向同一目标的多个 `FlagRef`（例如彼此的克隆）只会保留一个。
+/// 所有空的 `FlagRef` 视为同一身份。
+#[derive(Debug)]
+pub struct FlagRefSet<T: ?Sized> {
+    map: HashMap<usize, FlagRef<T>>,
+}
+
+impl<T: ?Sized> FlagRefSet<T> {
+    /// 创建一个空集合
+    pub fn new() -> Self {
+        Self { map: HashMap::new() }
     }
     
-    /// 尝试可变借用内部值。
-    ///
-    /// 详见 [`FlagRefOption`]
-    pub fn try_borrow_mut(&self) -> FlagRefOption<RefMut<'_, T>> {
-        dangling_then_return!(self.0.inner_ptr().as_ptr(), FlagRefOption::Empty);
-        if !self.is_enabled() {
-            return FlagRefOption::Disabled;
-        }
-        let borrow = unsafe { self.0.as_ref_unchecked().try_borrow_mut().ok() };
-        // 解包ManuallyDrop<T> → T
-        let borrow_unwrapped = borrow.map(|r| RefMut::map(r, |md| md.deref_mut()));
-        FlagRefOption::from_borrow(borrow_unwrapped)
+    /// 集合内元素数量
+    pub fn len(&self) -> usize {
+        self.map.len()
     }
     
-    /// 尝试复活 `FlagCell`
+    /// 集合是否为空
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    
+    /// 插入一个 `FlagRef`
     ///
-    /// 仅当前对应 `FlagCell` 销毁即数据逻辑禁用时，可复活，否则返回 `Disabled` 。
-    pub fn resurrect(&self) -> FlagRefOption<FlagCell<T>> {
-        dangling_then_return!(self.0.inner_ptr().as_ptr(),FlagRefOption::Empty);
-        if self.is_enabled() {
-            return FlagRefOption::Disabled;
+    /// 若集合中已存在指向同一目标的 `FlagRef`，丢弃传入值并返回 `false`
+    pub fn insert(&mut self, flag_ref: FlagRef<T>) -> bool {
+        match self.map.entry(flag_ref.0.addr()) {
+            hash_map::Entry::Occupied(_) => false,
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(flag_ref);
+                true
+            }
         }
-        unsafe { self.enable(); }
-        self.0.inc_ref_count();
-        FlagRefOption::Some(FlagCell::from_inner(self.0.inner_ptr()))
     }
     
-    /// 创建一个不指向任何内容的 `FlagRef`
+    /// 集合中是否存在指向与 `flag_ref` 相同目标的 `FlagRef`
+    pub fn contains(&self, flag_ref: &FlagRef<T>) -> bool {
+        self.map.contains_key(&flag_ref.0.addr())
+    }
+    
+    /// 移除并返回指向与 `flag_ref` 相同目标的 `FlagRef`
+    pub fn remove(&mut self, flag_ref: &FlagRef<T>) -> Option<FlagRef<T>> {
+        self.map.remove(&flag_ref.0.addr())
+    }
+    
+    /// 遍历集合内的 `FlagRef`，顺序不确定
+    pub fn iter(&self) -> hash_map::Values<'_, usize, FlagRef<T>> {
+        self.map.values()
+    }
+    
+    /// 移除所有目标不再可用的 `FlagRef`
     ///
-    /// 尝试调用任何方法都将返回 `Empty`
-    pub fn new() -> Self {
-        Self::EMPTY
+    /// 详见 [`retain_live`]
+    pub fn retain_live(&mut self) {
+        self.map.retain(|_, r| r.is_enabled());
     }
 }
 
-impl<T> Default for FlagRef<T>{
-    /// 创建一个不指向任何内容的 `FlagRef`
-    ///
-    /// 尝试调用任何方法都将返回 `Empty`
+impl<T: ?Sized> Default for FlagRefSet<T> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T> Drop for FlagRef<T> {
-    // 与FlagCell的drop严格互斥
-    fn drop(&mut self) {
-        let ptr = self.0.inner_ptr();
-        dangling_then_return!(ptr.as_ptr());
-        
-        let new_count = self.0.dec_ref_count();
-        if new_count == 0 {
-            // SAFETY: 计数0=Cell不存在=无其他引用，指针合法。
-            // new_count 首次归零意味着，内存未曾释放，这是唯一释放点。
-            unsafe {
-                // 修复：先手动析构ManuallyDrop包裹的T，再析构外层结构
-                let refcell = &mut (*ptr.as_ptr()).0;
-                let mut_man_drop = RefCell::get_mut(refcell);
-                ManuallyDrop::drop(mut_man_drop);
-                
-                // 析构剩余结构 + 释放内存
-                drop_in_place(ptr.as_ptr());
-                dealloc(
-                    ptr.as_ptr() as *mut u8,
-                    Layout::new::<(RefCell<ManuallyDrop<T>>, Cell<isize>)>()
-                );
-            }
+impl<T: ?Sized> FromIterator<FlagRef<T>> for FlagRefSet<T> {
+    fn from_iter<I: IntoIterator<Item = FlagRef<T>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: ?Sized> Extend<FlagRef<T>> for FlagRefSet<T> {
+    fn extend<I: IntoIterator<Item = FlagRef<T>>>(&mut self, iter: I) {
+        for flag_ref in iter {
+            self.insert(flag_ref);
         }
     }
 }
 
-impl<T> Clone for FlagRef<T> {
-    /// 克隆一个 FlagRef，使引用计数加一
-    fn clone(&self) -> Self {
-        self.0.inc_ref_count();
-        Self(InnerFlag(self.0.inner_ptr()))
+/// 以分配身份（核心结构地址）比较与哈希的显式包装
+///
+/// `FlagRef` 本身刻意不实现 [`PartialEq`]，需要以 `==` 表示“指向同一目标”时，显式包装为 `Identity`。
+/// 与 [`FlagRefSet`] 相同，所有空的 `FlagRef` 视为同一身份。注意复活前后仍是同一身份，
+/// 区分逻辑对象请使用 [`FlagRef::key`]。
+///
+/// ```compile_fail
+/// let cell = flag_cell::FlagCell::new(1);
+/// let _ = cell.flag_borrow() == cell.flag_borrow();
+/// ```
+#[repr(transparent)]
+#[derive(Debug, Clone)]
+pub struct Identity<R>(pub R);
+
+impl<R> Identity<R> {
+    /// 取出内部值
+    pub fn into_inner(self) -> R {
+        self.0
     }
 }
 
-// impl<T> !Send for FlagRef<T> {}
-// impl<T> !Sync for FlagRef<T> {}
+impl<R> Deref for Identity<R> {
+    type Target = R;
+    
+    fn deref(&self) -> &R {
+        &self.0
+    }
+}
+
+impl<T: ?Sized> PartialEq for Identity<FlagRef<T>> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.0.addr() == other.0.0.addr()
+    }
+}
+
+impl<T: ?Sized> Eq for Identity<FlagRef<T>> {}
+
+impl<T: ?Sized> Hash for Identity<FlagRef<T>> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.0.addr().hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    
+    fn panic_message(f: impl FnOnce()) -> String {
+        let payload = catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+        match payload.downcast::<String>() {
+            Ok(s) => *s,
+            Err(payload) => payload.downcast_ref::<&str>().unwrap().to_string(),
+        }
+    }
+    
+    #[test]
+    fn max_refs_is_isize_max() {
+        assert_eq!(FlagCell::<u8>::MAX_REFS, isize::MAX);
+    }
+    
+    #[test]
+    fn overflow_panic_message() {
+        let cell = FlagCell::new(1);
+        // 无法真的创建 isize::MAX 个引用，直接改写计数
+        cell.0.count_ref().set(FlagCell::<i32>::MAX_REFS);
+        let msg = panic_message(|| drop(cell.flag_borrow()));
+        assert_eq!(msg, "flag-cell: reference count overflow (max isize::MAX)");
+        cell.0.count_ref().set(-FlagCell::<i32>::MAX_REFS);
+        let msg = panic_message(|| drop(cell.flag_borrow()));
+        assert_eq!(msg, "flag-cell: reference count overflow (max isize::MAX)");
+        cell.0.count_ref().set(1);
+    }
+    
+    #[test]
+    fn decrement_at_zero_panic_message() {
+        let cell = FlagCell::new(1);
+        cell.0.count_ref().set(0);
+        let msg = panic_message(|| {
+            cell.0.dec_ref_count();
+        });
+        assert_eq!(msg, "flag-cell: reference count decremented at zero");
+        cell.0.count_ref().set(1);
+    }
+}
@@ -0,0 +1,57 @@
+use flag_cell::*;
+
+use std::any::Any;
+
+#[test]
+fn registry_of_two_types() {
+    let num = FlagCell::new(42_u32);
+    let name = FlagCell::new(String::from("plugin"));
+    let registry: Vec<FlagRef<dyn Any>> = vec![num.flag_borrow_any(), name.flag_borrow_any()];
+    assert_eq!(num.ref_count(), 1);
+    assert_eq!(name.ref_count(), 1);
+    
+    let mut it = registry.into_iter();
+    let num_ref = it.next().unwrap().downcast::<u32>().unwrap();
+    let name_ref = it.next().unwrap().downcast::<String>().unwrap();
+    // 计数直接转交，不增不减
+    assert_eq!(num.ref_count(), 1);
+    assert_eq!(name.ref_count(), 1);
+    
+    *num.borrow_mut() += 1;
+    name.borrow_mut().push_str("-a");
+    assert_eq!(*num_ref.try_borrow().unwrap(), 43);
+    assert_eq!(&*name_ref.try_borrow().unwrap(), "plugin-a");
+}
+
+#[test]
+fn downcast_wrong_type_returns_self() {
+    let cell = FlagCell::new(1_i64);
+    let any = cell.flag_borrow_any();
+    let any = any.downcast::<String>().unwrap_err();
+    let any = any.downcast::<i32>().unwrap_err();
+    assert_eq!(cell.ref_count(), 1);
+    let r = any.downcast::<i64>().unwrap();
+    assert_eq!(*r.try_borrow().unwrap(), 1);
+}
+
+#[test]
+fn downcast_while_mutably_borrowed_fails() {
+    let cell = FlagCell::new(1_u8);
+    let any = cell.flag_borrow_any();
+    let guard = cell.borrow_mut();
+    let any = any.downcast::<u8>().unwrap_err();
+    drop(guard);
+    assert!(any.downcast::<u8>().is_ok());
+}
+
+#[test]
+fn erased_ref_observes_disable() {
+    let cell = FlagCell::new(0_u16);
+    let any = cell.flag_borrow_any();
+    cell.disable();
+    assert!(!any.is_enabled());
+    drop(cell);
+    let r = any.downcast::<u16>().unwrap();
+    assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+    assert!(r.is_last());
+}
@@ -0,0 +1,35 @@
+use flag_cell::*;
+use std::cell::Cell;
+
+#[test]
+fn from_fn_reads_through_ref() {
+    let cell = FlagCell::<[u32; 16]>::from_fn(|i| i as u32 * 2);
+    let r = cell.flag_borrow();
+    let arr = r.try_borrow().unwrap();
+    assert_eq!(arr[0], 0);
+    assert_eq!(arr[7], 14);
+    assert_eq!(arr[15], 30);
+}
+
+#[test]
+fn from_fn_panic_drops_written_prefix() {
+    thread_local! {
+        static DROPS: Cell<usize> = const { Cell::new(0) };
+    }
+    struct Counted;
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.with(|d| d.set(d.get() + 1));
+        }
+    }
+    let res = std::panic::catch_unwind(|| {
+        FlagCell::<[Counted; 8]>::from_fn(|i| {
+            if i == 5 {
+                panic!("stop");
+            }
+            Counted
+        })
+    });
+    assert!(res.is_err());
+    assert_eq!(DROPS.with(Cell::get), 5);
+}
@@ -0,0 +1,65 @@
+use flag_cell::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => payload.downcast_ref::<&str>().map(|s| s.to_string()).unwrap_or_default(),
+    }
+}
+
+#[test]
+fn conflict_panics_with_readable_message() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow_mut();
+    let payload = catch_unwind(AssertUnwindSafe(|| drop(cell.borrow()))).unwrap_err();
+    assert!(panic_message(payload).starts_with("already mutably borrowed"));
+    drop(_g);
+
+    let _g = cell.borrow();
+    let payload = catch_unwind(AssertUnwindSafe(|| drop(cell.borrow_mut()))).unwrap_err();
+    assert!(panic_message(payload).starts_with("already borrowed"));
+}
+
+#[test]
+fn catch_mutable_after_shared() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow();
+    let conflict = catch_borrow_conflict(|| drop(cell.borrow_mut())).unwrap_err();
+    assert_eq!(conflict.requested, BorrowKind::Mutable);
+    assert_eq!(conflict.held, BorrowKind::Shared);
+    assert_eq!(conflict.generation, cell.generation());
+}
+
+#[test]
+fn catch_shared_after_mutable() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow_mut();
+    let conflict = catch_borrow_conflict(|| drop(cell.borrow())).unwrap_err();
+    assert_eq!(conflict.requested, BorrowKind::Shared);
+    assert_eq!(conflict.held, BorrowKind::Mutable);
+    assert!(conflict.to_string().starts_with("already mutably borrowed"));
+}
+
+#[test]
+fn catch_passes_through_values_and_other_panics() {
+    let cell = FlagCell::new(1);
+    assert_eq!(catch_borrow_conflict(|| *cell.borrow() + 1).unwrap(), 2);
+    let other = catch_unwind(|| catch_borrow_conflict(|| panic!("unrelated")));
+    assert_eq!(panic_message(other.unwrap_err()), "unrelated");
+}
+
+#[test]
+fn conflict_caught_inside_does_not_leak_out() {
+    let cell = FlagCell::new(1);
+    let g = cell.borrow_mut();
+    let r = catch_borrow_conflict(|| {
+        let inner = catch_unwind(AssertUnwindSafe(|| drop(cell.borrow())));
+        assert!(inner.is_err());
+        7
+    });
+    assert_eq!(r.unwrap(), 7);
+    drop(g);
+    let other = catch_unwind(|| catch_borrow_conflict(|| panic!("later")));
+    assert_eq!(panic_message(other.unwrap_err()), "later");
+}
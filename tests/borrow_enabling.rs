@@ -0,0 +1,52 @@
+use flag_cell::*;
+
+#[test]
+fn disabled_but_present_becomes_borrowable() {
+    let cell = FlagCell::new(3);
+    let r = cell.flag_borrow();
+    cell.disable();
+    assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+    // SAFETY: 测试中有意越过禁用
+    match unsafe { r.borrow_enabling() } {
+        FlagRefOption::Some(b) => assert_eq!(*b, 3),
+        other => panic!("unexpected {other:?}"),
+    }
+    // 借用结束后保持启用
+    assert!(cell.is_enabled());
+    assert!(r.is_enabled());
+}
+
+#[test]
+fn enabled_path_is_plain_borrow() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    // SAFETY: 同上
+    assert!(matches!(unsafe { r.borrow_enabling() }, FlagRefOption::Some(_)));
+    let _g = cell.borrow_mut();
+    // SAFETY: 同上
+    assert!(matches!(unsafe { r.borrow_enabling() }, FlagRefOption::Conflict));
+}
+
+#[test]
+fn nested_depth_only_decrements_once() {
+    let cell = FlagCell::new_nested(1);
+    let r = cell.flag_borrow();
+    cell.disable();
+    cell.disable();
+    // SAFETY: 同上
+    assert!(matches!(unsafe { r.borrow_enabling() }, FlagRefOption::Disabled));
+    assert_eq!(cell.disable_depth(), 1);
+    // SAFETY: 同上
+    assert!(matches!(unsafe { r.borrow_enabling() }, FlagRefOption::Some(_)));
+    assert_eq!(cell.disable_depth(), 0);
+}
+
+#[test]
+fn dropped_payload_is_not_enabled() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    assert_eq!(cell.force_unwrap(), 1);
+    // SAFETY: 同上
+    assert!(matches!(unsafe { r.borrow_enabling() }, FlagRefOption::DataDropped));
+    assert!(!r.is_enabled());
+}
@@ -0,0 +1,73 @@
+use flag_cell::*;
+
+#[test]
+fn detailed_already_mutably_borrowed() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let _g = cell.borrow_mut();
+    assert_eq!(r.try_borrow_detailed().err(), Some(FlagBorrowError::AlreadyMutablyBorrowed));
+}
+
+#[test]
+fn detailed_already_borrowed() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let _g = cell.borrow();
+    assert_eq!(r.try_borrow_mut_detailed().err(), Some(FlagBorrowError::AlreadyBorrowed));
+    assert!(r.try_borrow_detailed().is_ok());
+}
+
+#[test]
+fn detailed_empty() {
+    let r = FlagRef::<i32>::new();
+    assert_eq!(r.try_borrow_detailed().err(), Some(FlagBorrowError::Empty));
+}
+
+#[test]
+fn detailed_disabled() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    cell.disable();
+    assert_eq!(r.try_borrow_detailed().err(), Some(FlagBorrowError::Disabled));
+    cell.enable();
+    assert_eq!(*r.try_borrow_detailed().unwrap(), 1);
+}
+
+#[test]
+fn result_forwards_borrow_error() {
+    let cell = FlagCell::new(String::from("a"));
+    {
+        let _g = cell.borrow_mut();
+        let err: std::cell::BorrowError = cell.try_borrow_result().unwrap_err();
+        assert_eq!(err.to_string(), cell.as_ref_cell_ref().try_borrow().unwrap_err().to_string());
+    }
+    assert_eq!(&*cell.try_borrow_result().unwrap(), "a");
+}
+
+#[test]
+fn result_forwards_borrow_mut_error() {
+    let cell = FlagCell::new(String::from("a"));
+    {
+        let _g = cell.borrow();
+        let err: std::cell::BorrowMutError = cell.try_borrow_mut_result().unwrap_err();
+        assert_eq!(err.to_string(), cell.as_ref_cell_ref().try_borrow_mut().unwrap_err().to_string());
+    }
+    cell.try_borrow_mut_result().unwrap().push('b');
+    assert_eq!(&*cell.borrow(), "ab");
+}
+
+#[test]
+fn assert_no_active_borrows_clean() {
+    let cell = FlagCell::new(1);
+    drop(cell.borrow());
+    drop(cell.borrow_mut());
+    cell.assert_no_active_borrows();
+}
+
+#[test]
+#[should_panic(expected = "FlagCell still has an active borrow")]
+fn assert_no_active_borrows_forgotten_guard() {
+    let cell = FlagCell::new(1);
+    std::mem::forget(cell.borrow());
+    cell.assert_no_active_borrows();
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "debug_borrow_tracking")]
+
+use flag_cell::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+fn panic_message(f: impl FnOnce()) -> String {
+    let payload = catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => payload.downcast_ref::<&str>().unwrap().to_string(),
+    }
+}
+
+#[test]
+fn borrow_mut_conflict_mentions_tracking() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow();
+    let msg = panic_message(|| drop(cell.borrow_mut()));
+    assert!(msg.contains("[debug_borrow_tracking]"), "{msg}");
+    assert!(msg.contains("conflicting borrow taken at"), "{msg}");
+}
+
+#[test]
+fn borrow_conflict_mentions_tracking() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow_mut();
+    let msg = panic_message(|| drop(cell.borrow()));
+    assert!(msg.contains("[debug_borrow_tracking]"), "{msg}");
+}
@@ -0,0 +1,23 @@
+use flag_cell::*;
+use std::fmt::Debug;
+
+#[test]
+fn replace_boxed_keeps_box_identity() {
+    let first: Box<dyn Debug> = Box::new(1);
+    let second: Box<dyn Debug> = Box::new("two");
+    let first_addr = &*first as *const dyn Debug as *const ();
+    let second_addr = &*second as *const dyn Debug as *const ();
+    let cell = FlagCell::new(first);
+    let old = cell.replace_boxed(second);
+    assert_eq!(&*old as *const dyn Debug as *const (), first_addr);
+    assert_eq!(&**cell.borrow() as *const dyn Debug as *const (), second_addr);
+    assert_eq!(format!("{:?}", cell.borrow()), "\"two\"");
+}
+
+#[test]
+#[should_panic]
+fn replace_boxed_panics_while_borrowed() {
+    let cell = FlagCell::new(Box::new(1));
+    let _g = cell.borrow();
+    let _ = cell.replace_boxed(Box::new(2));
+}
@@ -0,0 +1,30 @@
+use flag_cell::*;
+
+use std::collections::HashMap;
+
+#[test]
+fn vec_with_capacity() {
+    let cell = FlagCell::<Vec<u8>>::with_capacity(32);
+    assert!(cell.borrow().capacity() >= 32);
+    assert!(cell.borrow().is_empty());
+}
+
+#[test]
+fn string_with_capacity() {
+    let cell = FlagCell::<String>::with_capacity(16);
+    assert!(cell.borrow().capacity() >= 16);
+    assert!(cell.borrow().is_empty());
+}
+
+#[test]
+fn hash_map_with_capacity() {
+    let cell = FlagCell::<HashMap<u32, u32>>::with_capacity(10);
+    assert!(cell.borrow().capacity() >= 10);
+    assert!(cell.borrow().is_empty());
+}
+
+#[test]
+fn zero_capacity_does_not_allocate() {
+    assert_eq!(FlagCell::<Vec<u64>>::with_capacity(0).borrow().capacity(), 0);
+    assert_eq!(FlagCell::<String>::with_capacity(0).borrow().capacity(), 0);
+}
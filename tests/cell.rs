@@ -0,0 +1,285 @@
+use flag_cell::*;
+
+#[test]
+fn replace_if_predicate_true() {
+    let cell = FlagCell::new(1);
+    assert_eq!(cell.replace_if(|v| *v == 1, 2), Ok(1));
+    assert_eq!(*cell.borrow(), 2);
+}
+
+#[test]
+fn replace_if_predicate_false() {
+    let cell = FlagCell::new(String::from("a"));
+    assert_eq!(cell.replace_if(|v| v.is_empty(), String::from("b")), Err(String::from("b")));
+    assert_eq!(&*cell.borrow(), "a");
+}
+
+#[test]
+#[should_panic]
+fn replace_if_panics_while_borrowed() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow();
+    let _ = cell.replace_if(|_| true, 2);
+}
+
+#[test]
+fn extend_inner_grows_vec() {
+    let cell = FlagCell::new(vec![1]);
+    cell.extend_inner(2..=4);
+    cell.extend_inner([5]);
+    assert_eq!(*cell.borrow(), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn extend_inner_string() {
+    let cell = FlagCell::new(String::from("a"));
+    cell.extend_inner(['b', 'c']);
+    assert_eq!(&*cell.borrow(), "abc");
+}
+
+#[test]
+#[should_panic]
+fn extend_inner_panics_while_borrowed() {
+    let cell = FlagCell::new(vec![1]);
+    let _g = cell.borrow();
+    cell.extend_inner([2]);
+}
+
+#[test]
+fn nested_reentrant_reads_succeed() {
+    let cell = FlagCell::new(vec![1, 2, 3]);
+    let outer = cell.try_read_reentrant().unwrap();
+    let inner = cell.try_read_reentrant().unwrap();
+    let innermost = cell.try_read_reentrant().unwrap();
+    assert_eq!(outer.len() + inner.len() + innermost.len(), 9);
+}
+
+#[test]
+fn reentrant_read_fails_only_under_mut_borrow() {
+    let cell = FlagCell::new(1);
+    {
+        let _g = cell.borrow_mut();
+        assert!(cell.try_read_reentrant().is_none());
+    }
+    assert_eq!(*cell.try_read_reentrant().unwrap(), 1);
+}
+
+#[test]
+fn take_if_predicate_true() {
+    let cell = FlagCell::new(vec![1, 2]);
+    assert_eq!(cell.take_if(|v| v.len() == 2), Some(vec![1, 2]));
+    assert!(cell.borrow().is_empty());
+}
+
+#[test]
+fn take_if_predicate_false_keeps_mutation() {
+    let cell = FlagCell::new(vec![1]);
+    // 与 Option::take_if 相同，predicate 可以修改值
+    assert_eq!(cell.take_if(|v| {
+        v.push(2);
+        false
+    }), None);
+    assert_eq!(*cell.borrow(), [1, 2]);
+}
+
+#[test]
+#[should_panic]
+fn take_if_panics_while_borrowed() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow();
+    let _ = cell.take_if(|_| true);
+}
+
+#[test]
+fn get_or_init_runs_once() {
+    let cell = FlagCell::new(None::<String>);
+    let mut calls = 0;
+    for _ in 0..3 {
+        let v = cell.get_or_init(|| {
+            calls += 1;
+            String::from("init")
+        });
+        assert_eq!(&*v, "init");
+    }
+    assert_eq!(calls, 1);
+    assert_eq!(cell.borrow().as_deref(), Some("init"));
+}
+
+#[test]
+fn get_or_init_keeps_existing_value() {
+    let cell = FlagCell::new(Some(1));
+    assert_eq!(*cell.get_or_init(|| unreachable!()), 1);
+}
+
+#[test]
+fn get_or_init_allows_concurrent_shared_guards() {
+    let cell = FlagCell::new(None);
+    let a = cell.get_or_init(|| 5);
+    let b = cell.get_or_init(|| 6);
+    assert_eq!((*a, *b), (5, 5));
+}
+
+#[test]
+fn replace_with_default_returns_old() {
+    let cell = FlagCell::new(vec![1, 2]);
+    assert_eq!(cell.replace_with_default(), [1, 2]);
+    assert_eq!(*cell.borrow(), Vec::<i32>::new());
+    let cell = FlagCell::new(7u8);
+    assert_eq!(cell.replace_with_default(), 7);
+    assert_eq!(*cell.borrow(), 0);
+}
+
+#[test]
+#[should_panic]
+fn replace_with_default_panics_while_borrowed() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow();
+    let _ = cell.replace_with_default();
+}
+
+#[test]
+fn replace_ignores_enabled_state() {
+    let cell = FlagCell::new(1);
+    assert_eq!(cell.replace(2), 1);
+    cell.disable();
+    assert_eq!(cell.replace(3), 2);
+    assert_eq!(cell.try_replace(4), Ok(3));
+    assert!(!cell.is_enabled());
+    assert_eq!(*cell.borrow(), 4);
+}
+
+#[test]
+fn replace_enabled_only_is_gated() {
+    let cell = FlagCell::new(1);
+    assert_eq!(cell.replace_enabled_only(2), Ok(1));
+    cell.disable();
+    assert_eq!(cell.replace_enabled_only(3), Err(3));
+    assert_eq!(*cell.borrow(), 2);
+    cell.enable();
+    assert_eq!(cell.replace_enabled_only(3), Ok(2));
+}
+
+#[test]
+fn try_map_in_place_success_and_error() {
+    let cell = FlagCell::new(vec![1]);
+    assert_eq!(cell.try_map_in_place(|v| {
+        v.push(2);
+        Ok::<(), ()>(())
+    }), Ok(()));
+    assert_eq!(*cell.borrow(), [1, 2]);
+    // 无回滚：失败前的修改保留
+    assert_eq!(cell.try_map_in_place(|v| {
+        v.push(3);
+        Err("bad")
+    }), Err("bad"));
+    assert_eq!(*cell.borrow(), [1, 2, 3]);
+}
+
+#[test]
+fn try_map_in_place_cloned_rolls_back() {
+    let cell = FlagCell::new(vec![1]);
+    assert_eq!(cell.try_map_in_place_cloned(|v| {
+        v.push(2);
+        Err("bad")
+    }), Err("bad"));
+    assert_eq!(*cell.borrow(), [1]);
+    assert_eq!(cell.try_map_in_place_cloned(|v| {
+        v.push(2);
+        Ok::<(), ()>(())
+    }), Ok(()));
+    assert_eq!(*cell.borrow(), [1, 2]);
+}
+
+#[test]
+fn retry_succeeds_after_first_attempt() {
+    use std::cell::RefCell;
+    let cell = FlagCell::new(1);
+    let held = RefCell::new(Some(cell.borrow_mut()));
+    let mut pumps = 0;
+    let r = cell.try_borrow_with_retry(3, || {
+        pumps += 1;
+        // 模拟事件循环释放借用
+        held.borrow_mut().take();
+    });
+    assert_eq!(r.as_deref(), Some(&1));
+    assert_eq!(pumps, 1);
+}
+
+#[test]
+fn retry_gives_up() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow_mut();
+    let mut pumps = 0;
+    assert!(cell.try_borrow_with_retry(3, || pumps += 1).is_none());
+    assert_eq!(pumps, 2);
+    assert!(cell.try_borrow_with_retry(0, || unreachable!()).is_none());
+}
+
+#[test]
+fn borrow_map_or_success_and_fallback() {
+    let cell = FlagCell::new(vec![1, 2, 3]);
+    assert_eq!(cell.borrow_map_or(0, Vec::len), 3);
+    let _g = cell.borrow_mut();
+    assert_eq!(cell.borrow_map_or(0, Vec::len), 0);
+}
+
+#[test]
+fn borrow_map_or_strict_disabled_fallback() {
+    let cell = FlagCell::new_strict(5);
+    cell.disable();
+    assert_eq!(cell.borrow_map_or(-1, |v| *v), -1);
+    let lenient = FlagCell::new(5);
+    lenient.disable();
+    assert_eq!(lenient.borrow_map_or(-1, |v| *v), 5);
+}
+
+#[test]
+fn borrow_mut_if_changed_equal_prev() {
+    let cell = FlagCell::new(vec![1, 2]);
+    assert!(cell.borrow_mut_if_changed(&vec![1, 2]).is_none());
+    // 比较用的共享借用已释放
+    assert!(cell.try_borrow_mut().is_some());
+}
+
+#[test]
+fn borrow_mut_if_changed_differing_prev() {
+    let cell = FlagCell::new(3);
+    let mut guard = cell.borrow_mut_if_changed(&2).unwrap();
+    *guard += 1;
+    drop(guard);
+    assert_eq!(*cell.borrow(), 4);
+    assert!(cell.borrow_mut_if_changed(&4).is_none());
+}
+
+#[test]
+fn borrow_mut_if_changed_under_shared_borrow() {
+    let cell = FlagCell::new(1);
+    let r = cell.borrow();
+    assert!(cell.borrow_mut_if_changed(&0).is_none());
+    drop(r);
+    assert!(cell.borrow_mut_if_changed(&0).is_some());
+}
+
+#[test]
+fn try_replace_with_accepts() {
+    let cell = FlagCell::new(10_u32);
+    let old = cell.try_replace_with(|v| v.checked_sub(3).ok_or("underflow"));
+    assert_eq!(old, Ok(10));
+    assert_eq!(*cell.borrow(), 7);
+}
+
+#[test]
+fn try_replace_with_rejects() {
+    let cell = FlagCell::new(2_u32);
+    let res = cell.try_replace_with(|v| v.checked_sub(3).ok_or("underflow"));
+    assert_eq!(res, Err("underflow"));
+    assert_eq!(*cell.borrow(), 2);
+}
+
+#[test]
+#[should_panic]
+fn try_replace_with_panics_while_borrowed() {
+    let cell = FlagCell::new(1);
+    let _r = cell.borrow();
+    let _ = cell.try_replace_with(|v| Ok::<_, ()>(v + 1));
+}
@@ -0,0 +1,116 @@
+use flag_cell::*;
+
+#[test]
+fn tracked_borrow_bumps_version_by_one() {
+    let cell = FlagCell::new(0);
+    assert_eq!(cell.version(), 0);
+    for i in 1..=3 {
+        *cell.borrow_mut_tracked() += 1;
+        assert_eq!(cell.version(), i);
+    }
+    // 未修改也计为一次
+    drop(cell.borrow_mut_tracked());
+    assert_eq!(cell.version(), 4);
+    // 普通借用不改变版本
+    *cell.borrow_mut() += 1;
+    assert_eq!(cell.version(), 4);
+}
+
+#[test]
+fn tracked_borrow_keeps_identity() {
+    let cell = FlagCell::new(0);
+    let r = cell.flag_borrow();
+    let handle = r.to_handle();
+    let key = r.key();
+    let generation = cell.generation();
+    *cell.borrow_mut_tracked() = 1;
+    cell.replace_notify(2);
+    assert_eq!(cell.generation(), generation);
+    assert_eq!(r.key(), key);
+    assert!(handle.same_logical_object(&cell));
+    // SAFETY: cell 仍存活
+    assert!(matches!(unsafe { FlagRef::upgrade_handle(handle) }, FlagRefOption::Some(_)));
+}
+
+#[test]
+fn change_token_sees_tracked_changes_and_resurrection() {
+    let cell = FlagCell::new(String::new());
+    let token = cell.change_token();
+    assert!(!cell.has_changed_since(token));
+    cell.borrow_mut().push('a');
+    assert!(!cell.has_changed_since(token));
+    cell.borrow_mut_tracked().push('b');
+    assert!(cell.has_changed_since(token));
+
+    let token = cell.change_token();
+    assert_eq!(cell.replace_notify(String::from("c")), "ab");
+    assert!(cell.has_changed_since(token));
+
+    let r = cell.flag_borrow();
+    let token = cell.change_token();
+    drop(cell);
+    let cell = r.resurrect().unwrap();
+    assert!(cell.has_changed_since(token));
+}
+
+#[test]
+fn resurrection_bumps_generation_only() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let handle = r.to_handle();
+    drop(cell);
+    let cell = r.resurrect().unwrap();
+    assert_eq!(cell.version(), 0);
+    assert_eq!(r.generation(), Some(1));
+    assert!(!handle.same_logical_object(&cell));
+    // SAFETY: cell 仍存活
+    assert!(matches!(unsafe { FlagRef::upgrade_handle(handle) }, FlagRefOption::Empty));
+}
+
+#[test]
+fn cached_handle_detects_resurrection() {
+    let cell = FlagCell::new(1);
+    let cached = cell.flag_borrow();
+    let handle = cached.to_handle();
+    assert!(handle.same_logical_object(&cell));
+    drop(cell);
+    let cell = cached.resurrect().unwrap();
+    assert!(!handle.same_logical_object(&cell));
+    assert!(cached.to_handle().same_logical_object(&cell));
+}
+
+#[test]
+fn subscriber_polls_version_through_ref() {
+    let cell = FlagCell::new(0);
+    let subscriber = cell.flag_borrow();
+    let seen = subscriber.version().unwrap();
+    *cell.borrow_mut_tracked() = 1;
+    assert_eq!(subscriber.version(), Some(seen + 1));
+    assert_eq!(FlagRef::<i32>::new().version(), None);
+}
+
+#[test]
+fn change_token_is_copy_and_stable() {
+    let cell = FlagCell::new(0);
+    let token = cell.change_token();
+    let copy = token;
+    assert_eq!(token, cell.change_token());
+    *cell.borrow_mut_tracked() += 1;
+    assert!(cell.has_changed_since(copy));
+    assert!(cell.has_changed_since(token));
+    assert_ne!(token, cell.change_token());
+}
+
+#[test]
+fn subscriber_sees_replace_notify_but_not_replace() {
+    let cell = FlagCell::new(1);
+    let subscriber = cell.flag_borrow();
+    let token = cell.change_token();
+    let version = subscriber.version();
+    assert_eq!(cell.replace(2), 1);
+    assert!(!cell.has_changed_since(token));
+    assert_eq!(subscriber.version(), version);
+    assert_eq!(cell.replace_notify(3), 2);
+    assert!(cell.has_changed_since(token));
+    assert_eq!(subscriber.version(), version.map(|v| v + 1));
+}
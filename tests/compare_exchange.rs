@@ -0,0 +1,45 @@
+use flag_cell::*;
+
+#[test]
+fn matching_current_flips_and_keeps_count() {
+    let cell = FlagCell::new(1);
+    let _r = cell.flag_borrow();
+    assert_eq!(cell.compare_exchange_enabled(true, false), Ok(true));
+    assert!(!cell.is_enabled());
+    assert_eq!(cell.ref_count(), 1);
+    assert_eq!(cell.compare_exchange_enabled(false, true), Ok(false));
+    assert!(cell.is_enabled());
+    assert_eq!(cell.ref_count(), 1);
+    // old == new 时同样成功，不作修改
+    assert_eq!(cell.compare_exchange_enabled(true, true), Ok(true));
+}
+
+#[test]
+fn mismatching_current_fails() {
+    let cell = FlagCell::new(1);
+    assert_eq!(cell.compare_exchange_enabled(false, true), Err(true));
+    assert!(cell.is_enabled());
+    cell.disable();
+    assert_eq!(cell.compare_exchange_enabled(true, false), Err(false));
+    assert!(!cell.is_enabled());
+}
+
+#[test]
+fn lease_blocks_compare_exchange_disable() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let _lease = r.upgrade_strong().unwrap();
+    assert_eq!(cell.compare_exchange_enabled(true, false), Err(true));
+    assert!(cell.is_enabled());
+}
+
+#[test]
+fn nested_compare_exchange_resets_depth() {
+    let cell = FlagCell::new_nested(1);
+    cell.disable();
+    cell.disable();
+    assert_eq!(cell.compare_exchange_enabled(false, true), Ok(false));
+    assert_eq!(cell.disable_depth(), 0);
+    assert_eq!(cell.compare_exchange_enabled(true, false), Ok(true));
+    assert_eq!(cell.disable_depth(), 1);
+}
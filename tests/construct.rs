@@ -0,0 +1,12 @@
+use flag_cell::*;
+
+#[test]
+fn new_with_ref_counts_the_ref() {
+    let (cell, r) = FlagCell::new_with_ref(String::from("x"));
+    assert_eq!(cell.ref_count(), 1);
+    assert_eq!(r.ref_count(), 1);
+    assert!(r.to_handle().same_logical_object(&cell));
+    drop(r);
+    assert_eq!(cell.ref_count(), 0);
+    assert_eq!(cell.try_unwrap().ok().as_deref(), Some("x"));
+}
@@ -0,0 +1,29 @@
+use flag_cell::*;
+
+#[test]
+fn cell_style_get_set_replace() {
+    let cell = FlagCell::new(1u32);
+    assert_eq!(cell.get(), 1);
+    cell.set(2);
+    assert_eq!(cell.get(), 2);
+    assert_eq!(cell.replace(3), 2);
+    assert_eq!(cell.get(), 3);
+}
+
+#[test]
+fn cell_style_swap() {
+    let a = FlagCell::new((1, 'a'));
+    let b = FlagCell::new((2, 'b'));
+    a.swap(&b);
+    assert_eq!((a.get(), b.get()), ((2, 'b'), (1, 'a')));
+    // 与自身交换为无操作
+    a.swap(&a);
+    assert_eq!(a.get(), (2, 'b'));
+}
+
+#[test]
+fn get_works_alongside_shared_borrow() {
+    let cell = FlagCell::new(5i64);
+    let _g = cell.borrow();
+    assert_eq!(cell.get(), 5);
+}
@@ -0,0 +1,68 @@
+use flag_cell::*;
+
+#[test]
+fn borrow_cow_borrowed_then_read() {
+    let cell = FlagCell::new(vec![1, 2]);
+    let cow = cell.borrow_cow();
+    assert!(cow.is_borrowed());
+    assert_eq!(cow.len(), 2);
+    // 借用期间不可再可变借用
+    assert!(cell.try_borrow_mut().is_none());
+    drop(cow);
+    assert!(cell.try_borrow_mut().is_some());
+}
+
+#[test]
+fn borrow_cow_into_owned() {
+    let cell = FlagCell::new(vec![1, 2]);
+    let mut owned = cell.borrow_cow().into_owned();
+    owned.push(3);
+    assert_eq!(*cell.borrow(), [1, 2]);
+    assert_eq!(owned, [1, 2, 3]);
+}
+
+#[test]
+fn borrow_cow_to_mut_releases_borrow() {
+    let cell = FlagCell::new(String::from("a"));
+    let mut cow = cell.borrow_cow();
+    cow.to_mut().push('b');
+    assert!(!cow.is_borrowed());
+    cell.borrow_mut().push('c');
+    assert_eq!(&*cow, "ab");
+    assert_eq!(&*cell.borrow(), "ac");
+}
+
+#[test]
+fn borrow_mut_or_clone_direct_path() {
+    let cell = FlagCell::new(vec![1]);
+    let mut c = cell.borrow_mut_or_clone();
+    assert!(c.is_borrowed());
+    c.push(2);
+    assert!(cell.try_borrow().is_none());
+    assert!(c.commit().is_ok());
+    assert_eq!(*cell.borrow(), [1, 2]);
+}
+
+#[test]
+fn borrow_mut_or_clone_then_commit() {
+    let cell = FlagCell::new(vec![1]);
+    let reader = cell.borrow();
+    let mut c = cell.borrow_mut_or_clone();
+    assert!(!c.is_borrowed());
+    c.push(2);
+    // 修改只作用于克隆
+    assert_eq!(*reader, [1]);
+    let c = c.commit().unwrap_err();
+    assert_eq!(*c, [1, 2]);
+    drop(reader);
+    assert!(c.commit().is_ok());
+    assert_eq!(*cell.borrow(), [1, 2]);
+}
+
+#[test]
+#[should_panic(expected = "already mutably borrowed")]
+fn borrow_mut_or_clone_panics_under_mut_borrow() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow_mut();
+    let _ = cell.borrow_mut_or_clone();
+}
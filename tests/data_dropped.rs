@@ -0,0 +1,39 @@
+use flag_cell::*;
+
+#[test]
+fn flag_ref_option_is_debug() {
+    let mut cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    assert_eq!(format!("{:?}", r.try_borrow().map(|b| *b)), "Some(1)");
+    cell.drop_payload();
+    assert_eq!(format!("{:?}", r.try_borrow()), "DataDropped");
+}
+
+#[test]
+fn dropped_payload_with_flag_forced_enabled() {
+    let mut cell = FlagCell::new(String::from("payload"));
+    let r = cell.flag_borrow();
+    assert!(cell.drop_payload());
+    // SAFETY: 仅用于构造“数据已析构但标记仍为启用”的状态
+    unsafe { r.enable() };
+    assert!(r.is_enabled());
+    assert!(matches!(r.try_borrow(), FlagRefOption::DataDropped));
+    assert!(matches!(r.try_borrow_mut(), FlagRefOption::DataDropped));
+    assert_eq!(r.try_borrow_detailed().err(), Some(FlagBorrowError::DataDropped));
+    assert_eq!(r.try_borrow_mut_detailed().err(), Some(FlagBorrowError::DataDropped));
+    assert!(matches!(r.borrow_or_resurrect(), FlagRefOption::DataDropped));
+    assert!(cell.try_borrow().is_none());
+    assert!(cell.try_borrow_mut().is_none());
+}
+
+#[test]
+fn dropped_payload_blocks_resurrect_and_into_owner() {
+    let cell = FlagCell::new(vec![1, 2, 3]);
+    let r = cell.flag_borrow();
+    assert_eq!(cell.force_unwrap(), [1, 2, 3]);
+    assert!(matches!(r.resurrect(), FlagRefOption::DataDropped));
+    // SAFETY: 同上
+    unsafe { r.enable() };
+    assert!(matches!(r.try_borrow(), FlagRefOption::DataDropped));
+    assert!(matches!(r.into_owner(), FlagRefOption::DataDropped));
+}
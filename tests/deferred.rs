@@ -0,0 +1,66 @@
+use flag_cell::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+struct Counted(&'static AtomicUsize);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn nested_deferred_dropped_at_thread_exit() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    thread::spawn(|| {
+        let outer = FlagCell::new_deferred(FlagCell::new_deferred(Counted(&DROPS)));
+        drop(outer);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+    })
+    .join()
+    .unwrap();
+    assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn nested_deferred_drained_manually() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    thread::spawn(|| {
+        drop(FlagCell::new_deferred(FlagCell::new_deferred(Counted(&DROPS))));
+        assert_eq!(drain_deferred(), 2);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+        assert_eq!(drain_deferred(), 0);
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn destructor_runs_only_after_drain() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    thread::spawn(|| {
+        let cell = FlagCell::new_deferred(Counted(&DROPS));
+        let r = cell.flag_borrow();
+        drop(cell);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+        drop(r);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 0);
+        assert_eq!(drain_deferred(), 1);
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn plain_cells_are_not_deferred() {
+    static DROPS: AtomicUsize = AtomicUsize::new(0);
+    thread::spawn(|| {
+        drop(FlagCell::new(Counted(&DROPS)));
+        assert_eq!(DROPS.load(Ordering::SeqCst), 1);
+        assert_eq!(drain_deferred(), 0);
+    })
+    .join()
+    .unwrap();
+}
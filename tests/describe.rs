@@ -0,0 +1,52 @@
+use flag_cell::*;
+
+#[test]
+fn cell_describe_reports_count_and_enabled() {
+    let cell = FlagCell::new(1);
+    let _a = cell.flag_borrow();
+    let _b = cell.flag_borrow();
+    let s = cell.describe();
+    assert!(s.starts_with("FlagCell@0x"), "{s}");
+    assert!(s.contains("enabled=true"), "{s}");
+    assert!(s.contains("refs=2"), "{s}");
+    assert!(s.contains("gen=0"), "{s}");
+    cell.disable();
+    assert!(cell.describe().contains("enabled=false"));
+}
+
+#[test]
+fn ref_describe_reports_count_and_enabled() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let s = r.describe();
+    assert!(s.starts_with("FlagRef@0x"), "{s}");
+    assert!(s.contains("enabled=true refs=1"), "{s}");
+    drop(cell);
+    assert!(r.describe().contains("enabled=false refs=1"));
+    assert_eq!(FlagRef::<i32>::new().describe(), "FlagRef@empty");
+}
+
+#[test]
+fn cell_and_ref_share_address() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let addr = |s: &str| s.split_whitespace().next().unwrap().split('@').nth(1).unwrap().to_owned();
+    assert_eq!(addr(&cell.describe()), addr(&r.describe()));
+}
+
+#[test]
+fn pointer_format_is_identity() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(1);
+    let r1 = a.flag_borrow();
+    let r2 = r1.clone();
+    assert_eq!(format!("{r1:p}"), format!("{r2:p}"));
+    assert_eq!(format!("{a:p}"), format!("{r1:p}"));
+    assert_ne!(format!("{a:p}"), format!("{b:p}"));
+}
+
+#[test]
+fn empty_ref_pointer_is_sentinel() {
+    let empty = FlagRef::<i32>::new();
+    assert_eq!(format!("{empty:p}"), format!("{:#x}", usize::MAX));
+}
@@ -0,0 +1,136 @@
+use flag_cell::*;
+
+#[test]
+fn map_cloned_escapes_the_guard() {
+    let cell = FlagCell::new(vec![1, 2, 3]);
+    let r = cell.flag_borrow();
+    let sum = r.map_cloned(|v| v.iter().sum::<i32>());
+    assert!(matches!(sum, FlagRefOption::Some(6)));
+    let first = r.map_cloned(|v| v.first().copied());
+    // 借用已释放，可以立即可变借用
+    cell.borrow_mut().push(4);
+    assert!(matches!(first, FlagRefOption::Some(Some(1))));
+    assert!(matches!(r.map_cloned(|v| v.len()), FlagRefOption::Some(4)));
+}
+
+#[test]
+fn map_cloned_failure_variants() {
+    let cell = FlagCell::new(String::from("a"));
+    let r = cell.flag_borrow();
+    {
+        let _g = cell.borrow_mut();
+        assert!(matches!(r.map_cloned(|s| s.clone()), FlagRefOption::Conflict));
+    }
+    cell.disable();
+    assert!(matches!(r.map_cloned(|s| s.clone()), FlagRefOption::Disabled));
+    assert!(matches!(FlagRef::<String>::new().map_cloned(|s| s.clone()), FlagRefOption::Empty));
+}
+
+#[test]
+fn value_eq_equal_and_unequal() {
+    let a = FlagCell::new(String::from("x"));
+    let b = FlagCell::new(String::from("x"));
+    let c = FlagCell::new(String::from("y"));
+    let (ra, rb, rc) = (a.flag_borrow(), b.flag_borrow(), c.flag_borrow());
+    assert!(matches!(ra.value_eq(&rb), FlagRefOption::Some(true)));
+    assert!(matches!(ra.value_eq(&rc), FlagRefOption::Some(false)));
+    assert!(matches!(ra.try_value_eq(&rc), FlagRefOption::Some(false)));
+}
+
+#[test]
+fn value_eq_self_comparison() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    assert!(matches!(r.value_eq(&r), FlagRefOption::Some(true)));
+    assert!(matches!(r.value_eq(&r.clone()), FlagRefOption::Some(true)));
+    // 已有共享借用时同样可以比较
+    let _g = cell.borrow();
+    assert!(matches!(r.value_eq(&r), FlagRefOption::Some(true)));
+}
+
+#[test]
+fn value_eq_failure_variants() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(1);
+    let (ra, rb) = (a.flag_borrow(), b.flag_borrow());
+    b.disable();
+    assert!(matches!(ra.value_eq(&rb), FlagRefOption::Disabled));
+    let _g = a.borrow_mut();
+    assert!(matches!(ra.value_eq(&rb), FlagRefOption::Conflict));
+}
+
+#[test]
+fn try_value_eq_live_and_dangling() {
+    let a = FlagCell::new(3);
+    let b = FlagCell::new(3);
+    let c = FlagCell::new(4);
+    let (ra, rb, rc) = (a.flag_borrow(), b.flag_borrow(), c.flag_borrow());
+    assert!(matches!(ra.try_value_eq(&rb), FlagRefOption::Some(true)));
+    assert!(matches!(ra.try_value_eq(&rc), FlagRefOption::Some(false)));
+    let empty = FlagRef::<i32>::new();
+    assert!(matches!(ra.try_value_eq(&empty), FlagRefOption::Empty));
+    assert!(matches!(empty.try_value_eq(&ra), FlagRefOption::Empty));
+}
+
+#[test]
+fn other_ref_count_excludes_self() {
+    let cell = FlagCell::new(1);
+    let a = cell.flag_borrow();
+    assert_eq!(a.ref_count(), 1);
+    assert_eq!(a.other_ref_count(), 0);
+    let b = a.clone();
+    let c = a.clone();
+    assert_eq!(a.other_ref_count(), 2);
+    drop(b);
+    assert_eq!(c.other_ref_count(), 1);
+    drop(cell);
+    // 不计入已销毁的 FlagCell
+    assert_eq!(c.other_ref_count(), 1);
+    assert_eq!(FlagRef::<i32>::new().other_ref_count(), 0);
+}
+
+#[test]
+fn is_last_with_multiple_refs() {
+    let cell = FlagCell::new(1);
+    let r1 = cell.flag_borrow();
+    let r2 = r1.clone();
+    drop(cell);
+    assert!(!r1.is_last());
+    assert!(!r2.is_last());
+    drop(r2);
+    assert!(r1.is_last());
+}
+
+#[test]
+fn is_last_sole_ref_with_owner_alive() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    assert!(!r.is_last());
+    drop(cell);
+    assert!(r.is_last());
+}
+
+#[test]
+fn is_last_with_outstanding_lease() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let lease = cell.lease();
+    drop(cell);
+    assert!(!r.is_last());
+    drop(lease);
+    assert!(r.is_last());
+}
+
+#[test]
+fn is_last_after_resurrect_or_dangling() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    drop(cell);
+    let FlagRefOption::Some(owner) = r.resurrect() else {
+        panic!("resurrect failed");
+    };
+    assert!(!r.is_last());
+    drop(owner);
+    assert!(r.is_last());
+    assert!(!FlagRef::<i32>::new().is_last());
+}
@@ -0,0 +1,119 @@
+use flag_cell::*;
+
+#[test]
+fn zip_both_some() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new("b");
+    let (ra, rb) = (a.flag_borrow(), b.flag_borrow());
+    match ra.try_borrow().zip(rb.try_borrow()) {
+        FlagRefOption::Some((x, y)) => assert_eq!((*x, *y), (1, "b")),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn zip_one_disabled() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(2);
+    let (ra, rb) = (a.flag_borrow(), b.flag_borrow());
+    b.disable();
+    assert!(matches!(ra.try_borrow().zip(rb.try_borrow()), FlagRefOption::Disabled));
+    assert!(matches!(rb.try_borrow().zip(ra.try_borrow()), FlagRefOption::Disabled));
+}
+
+#[test]
+fn zip_both_conflict_and_priority() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(2);
+    let (ra, rb) = (a.flag_borrow(), b.flag_borrow());
+    let _ga = a.borrow_mut();
+    let _gb = b.borrow_mut();
+    assert!(matches!(ra.try_borrow().zip(rb.try_borrow()), FlagRefOption::Conflict));
+    // 均非 Some 时优先 self 的变体
+    b.disable();
+    assert!(matches!(ra.try_borrow().zip(rb.try_borrow()), FlagRefOption::Conflict));
+    assert!(matches!(rb.try_borrow().zip(ra.try_borrow()), FlagRefOption::Disabled));
+    assert!(matches!(FlagRef::<i32>::new().try_borrow().zip(rb.try_borrow()), FlagRefOption::Empty));
+}
+
+struct Point {
+    x: i32,
+    name: String,
+}
+
+#[test]
+fn map_deref_projects_a_field() {
+    let cell = FlagCell::new(Point { x: 3, name: String::from("p") });
+    let r = cell.flag_borrow();
+    let x = r.try_borrow().map_deref(|p| &p.x);
+    assert!(matches!(&x, FlagRefOption::Some(v) if **v == 3));
+    // 投影后的守卫仍持有借用
+    assert!(cell.try_borrow_mut().is_none());
+    drop(x);
+    let name = r.try_borrow().map_deref(|p| p.name.as_str());
+    assert_eq!(name.into_option().as_deref(), Some("p"));
+}
+
+#[test]
+fn map_deref_keeps_failure_variant() {
+    let cell = FlagCell::new(Point { x: 3, name: String::new() });
+    let r = cell.flag_borrow();
+    cell.disable();
+    assert!(matches!(r.try_borrow().map_deref(|p| &p.x), FlagRefOption::Disabled));
+}
+
+fn some(v: i32) -> FlagRefOption<i32> {
+    FlagRefOption::Some(v)
+}
+
+#[test]
+fn and_truth_table() {
+    assert!(matches!(some(1).and(some(2)), FlagRefOption::Some(2)));
+    assert!(matches!(some(1).and(FlagRefOption::<i32>::Disabled), FlagRefOption::Disabled));
+    assert!(matches!(FlagRefOption::<i32>::Conflict.and(some(2)), FlagRefOption::Conflict));
+    assert!(matches!(FlagRefOption::<i32>::Empty.and(FlagRefOption::<i32>::Disabled), FlagRefOption::Empty));
+}
+
+#[test]
+fn xor_truth_table() {
+    assert!(matches!(some(1).xor(some(2)), FlagRefOption::Conflict));
+    assert!(matches!(some(1).xor(FlagRefOption::Disabled), FlagRefOption::Some(1)));
+    assert!(matches!(FlagRefOption::Disabled.xor(some(2)), FlagRefOption::Some(2)));
+    assert!(matches!(FlagRefOption::<i32>::Empty.xor(FlagRefOption::Disabled), FlagRefOption::Empty));
+}
+
+static DEFAULT_NAME: &str = "default";
+
+#[test]
+fn unwrap_or_static_some_and_disabled() {
+    let cell = FlagCell::new("live");
+    let r = cell.flag_borrow();
+    let v = r.try_borrow().unwrap_or_static(&DEFAULT_NAME);
+    assert!(v.is_borrowed());
+    assert_eq!(*v, "live");
+    drop(v);
+    cell.disable();
+    let v = r.try_borrow().unwrap_or_static(&DEFAULT_NAME);
+    assert!(!v.is_borrowed());
+    assert_eq!(*v, "default");
+    assert_eq!(*FlagRef::<&str>::new().try_borrow().unwrap_or_static(&DEFAULT_NAME), "default");
+}
+
+#[test]
+fn unwrap_or_static_data_dropped() {
+    let cell = FlagCell::new("taken");
+    let r = cell.flag_borrow();
+    assert_eq!(cell.force_unwrap(), "taken");
+    let v = r.try_borrow().unwrap_or_static(&DEFAULT_NAME);
+    assert!(!v.is_borrowed());
+    assert_eq!(*v, "default");
+}
+
+#[test]
+fn or_empty_ref_matches_into_option() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    assert_eq!(r.try_borrow().or_empty_ref().as_deref(), Some(&1));
+    cell.disable();
+    assert!(r.try_borrow().or_empty_ref().is_none());
+}
@@ -0,0 +1,37 @@
+use flag_cell::*;
+
+#[test]
+fn collect_dedups_clones() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let set: FlagRefSet<i32> = vec![r.clone(), r.clone(), cell.flag_borrow()].into_iter().collect();
+    assert_eq!(set.len(), 1);
+    assert!(set.contains(&r));
+}
+
+#[test]
+fn insert_and_extend_by_identity() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(1);
+    let mut set = FlagRefSet::new();
+    assert!(set.insert(a.flag_borrow()));
+    assert!(!set.insert(a.flag_borrow()));
+    set.extend([b.flag_borrow(), b.flag_borrow(), a.flag_borrow()]);
+    assert_eq!(set.len(), 2);
+    // 重复项被丢弃，不会多占引用数量
+    assert_eq!(a.ref_count(), 1);
+    assert_eq!(b.ref_count(), 1);
+    assert!(set.remove(&b.flag_borrow()).is_some());
+    assert_eq!(set.len(), 1);
+}
+
+#[test]
+fn set_retain_live() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(2);
+    let mut set: FlagRefSet<i32> = [a.flag_borrow(), b.flag_borrow()].into_iter().collect();
+    drop(b);
+    set.retain_live();
+    assert_eq!(set.len(), 1);
+    assert!(set.contains(&a.flag_borrow()));
+}
@@ -0,0 +1,30 @@
+use flag_cell::*;
+use std::thread;
+
+fn assert_send<T: Send>() {}
+fn assert_sync<T: Sync>() {}
+
+#[test]
+fn wrappers_implement_marker_traits() {
+    assert_send::<ForceSend<FlagRef<u8>>>();
+    assert_send::<ForceSend<FlagCell<String>>>();
+    assert_sync::<ForceSync<FlagRef<u8>>>();
+}
+
+#[test]
+fn move_a_whole_group_to_another_thread_and_back() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    // SAFETY: FlagCell 与唯一的 FlagRef 一同移动，原线程不再持有任何句柄
+    let group = unsafe { ForceSend::new((cell, r)) };
+    let group = thread::spawn(move || {
+        *group.1.try_borrow_mut().unwrap() += 1;
+        group
+    })
+    .join()
+    .unwrap();
+    let (cell, r) = group.into_inner();
+    assert_eq!(*cell.borrow(), 2);
+    drop(r);
+    assert_eq!(cell.try_unwrap().ok(), Some(2));
+}
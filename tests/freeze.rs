@@ -0,0 +1,61 @@
+use flag_cell::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[test]
+fn frozen_cell_rejects_scope() {
+    let cell = FlagCell::new(1);
+    cell.freeze();
+    let r = catch_unwind(AssertUnwindSafe(|| cell.scope(|v| *v = 99)));
+    assert!(r.is_err());
+    assert_eq!(*cell.borrow(), 1);
+    assert!(cell.is_enabled());
+    cell.unfreeze();
+    cell.scope(|v| *v = 99);
+    assert_eq!(*cell.borrow(), 99);
+}
+
+#[test]
+fn frozen_cell_rejects_atomic_update() {
+    let cell = FlagCell::new(1);
+    cell.freeze();
+    assert!(catch_unwind(AssertUnwindSafe(|| cell.atomic_update(|v| *v = 2))).is_err());
+    assert_eq!(*cell.borrow(), 1);
+    assert!(cell.is_enabled());
+}
+
+#[test]
+fn mutation_fails_after_freeze_reads_succeed() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    assert!(!cell.is_frozen());
+    cell.freeze();
+    assert!(cell.is_frozen());
+    assert!(catch_unwind(AssertUnwindSafe(|| drop(cell.borrow_mut()))).is_err());
+    assert!(catch_unwind(AssertUnwindSafe(|| cell.replace(2))).is_err());
+    assert!(cell.try_borrow_mut().is_none());
+    assert_eq!(cell.try_replace(2), Err(2));
+    assert!(cell.try_borrow_mut_result().is_err());
+    assert!(matches!(r.try_borrow_mut(), FlagRefOption::Conflict));
+    assert_eq!(r.try_borrow_mut_detailed().err(), Some(FlagBorrowError::Frozen));
+    // 只读借用不受影响
+    assert_eq!(*cell.borrow(), 1);
+    assert_eq!(*cell.try_borrow().unwrap(), 1);
+    assert_eq!(*r.try_borrow().unwrap(), 1);
+}
+
+#[test]
+fn unfreeze_restores_mutation() {
+    let cell = FlagCell::new(1);
+    cell.freeze();
+    cell.unfreeze();
+    assert!(!cell.is_frozen());
+    *cell.borrow_mut() = 2;
+    assert_eq!(cell.replace(3), 2);
+}
+
+#[test]
+fn frozen_cell_can_still_unwrap() {
+    let cell = FlagCell::new(String::from("a"));
+    cell.freeze();
+    assert_eq!(cell.try_unwrap().ok().as_deref(), Some("a"));
+}
@@ -0,0 +1,80 @@
+use flag_cell::*;
+
+#[test]
+fn fresh_handle_upgrades_to_counted_ref() {
+    let cell = FlagCell::new(5);
+    let handle = cell.flag_borrow().to_handle();
+    let copy = handle;
+    // SAFETY: cell 存活，核心结构未被释放
+    let r = match unsafe { FlagRef::upgrade_handle(copy) } {
+        FlagRefOption::Some(r) => r,
+        other => panic!("unexpected {other:?}"),
+    };
+    assert_eq!(cell.ref_count(), 1);
+    assert_eq!(*r.try_borrow().unwrap(), 5);
+    assert_eq!(handle.addr(), copy.addr());
+}
+
+#[test]
+fn stale_handle_after_resurrection_fails() {
+    let cell = FlagCell::new(5);
+    let keep = cell.flag_borrow();
+    let stale = keep.to_handle();
+    drop(cell);
+    let cell = keep.resurrect().unwrap();
+    // SAFETY: keep 与 cell 存活
+    assert!(matches!(unsafe { FlagRef::upgrade_handle(stale) }, FlagRefOption::Empty));
+    assert_eq!(cell.ref_count(), 1);
+    // SAFETY: 同上
+    assert!(matches!(unsafe { FlagRef::upgrade_handle(keep.to_handle()) }, FlagRefOption::Some(_)));
+}
+
+#[test]
+fn empty_handle_fails() {
+    let handle = FlagRef::<i32>::new().to_handle();
+    // SAFETY: 空句柄不会被解引用
+    assert!(matches!(unsafe { FlagRef::upgrade_handle(handle) }, FlagRefOption::Empty));
+}
+
+#[test]
+fn same_logical_object_distinguishes_cells() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(1);
+    let handle = a.flag_borrow().to_handle();
+    assert!(handle.same_logical_object(&a));
+    assert!(!handle.same_logical_object(&b));
+}
+
+#[test]
+fn key_changes_after_resurrection() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let key = r.key();
+    assert_eq!(r.clone().key(), key);
+    assert_eq!(r.to_handle().key(), key);
+    drop(cell);
+    let _cell = r.resurrect().unwrap();
+    assert_ne!(r.key(), key);
+    assert_eq!(r.to_handle().key(), r.key());
+}
+
+#[test]
+fn keys_of_distinct_cells_differ() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(1);
+    assert_ne!(a.flag_borrow().key(), b.flag_borrow().key());
+}
+
+#[test]
+fn identity_compares_by_address() {
+    use std::collections::HashSet;
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(1);
+    let ra = a.flag_borrow();
+    assert!(Identity(ra.clone()) == Identity(a.flag_borrow()));
+    assert!(Identity(ra.clone()) != Identity(b.flag_borrow()));
+    let set: HashSet<_> = [Identity(ra.clone()), Identity(ra.clone()), Identity(b.flag_borrow())].into_iter().collect();
+    assert_eq!(set.len(), 2);
+    // Deref 到内部的 FlagRef
+    assert_eq!(*Identity(ra).try_borrow().unwrap(), 1);
+}
@@ -0,0 +1,68 @@
+use flag_cell::*;
+
+#[cfg(all(target_pointer_width = "64", not(feature = "debug_borrow_tracking")))]
+#[test]
+fn header_size_with_default_features() {
+    assert_eq!(HEADER_SIZE, 48);
+}
+
+#[cfg(all(target_pointer_width = "64", not(feature = "debug_borrow_tracking")))]
+#[test]
+fn header_size_within_budget() {
+    assert!(HEADER_SIZE <= 6 * std::mem::size_of::<usize>());
+    // 小数据只多出数据本身（按对齐补齐）
+    assert_eq!(std::mem::size_of::<FlagHeader<u64>>(), HEADER_SIZE + 8);
+}
+
+#[test]
+fn header_size_is_nonzero() {
+    assert_ne!(HEADER_SIZE, 0);
+    assert_eq!(HEADER_SIZE, std::mem::size_of::<FlagHeader<()>>());
+}
+
+#[test]
+fn side_table_metadata_round_trips() {
+    let cell = FlagCell::new(());
+    assert_eq!(cell.tag(), 0);
+    cell.set_tag(7);
+    assert_eq!(cell.tag(), 7);
+    let r = cell.flag_borrow();
+    assert_eq!(r.tag(), Some(7));
+    {
+        let _lease = r.upgrade_strong().unwrap();
+        assert!(cell.disable().is_none());
+    }
+    assert!(cell.disable().is_some());
+    assert!(cell.enable().is_some());
+}
+
+#[test]
+fn nested_depth_lives_outside_the_header() {
+    let cell = FlagCell::new_nested(0u8);
+    assert_eq!(cell.disable_depth(), 0);
+    cell.disable();
+    cell.disable();
+    assert_eq!(cell.disable_depth(), 2);
+    cell.enable();
+    assert!(!cell.is_enabled());
+    cell.enable();
+    assert!(cell.is_enabled());
+    assert_eq!(cell.disable_depth(), 0);
+}
+
+#[cfg(all(target_pointer_width = "64", not(feature = "debug_borrow_tracking")))]
+#[test]
+fn data_offset_is_stable() {
+    assert_eq!(FlagHeader::<u32>::DATA_OFFSET, 40);
+    assert_eq!(FlagHeader::<[u64; 4]>::DATA_OFFSET, 40);
+}
+
+#[test]
+fn data_field_is_last() {
+    use std::cell::RefCell;
+    use std::mem::{size_of, ManuallyDrop};
+    assert_eq!(
+        FlagHeader::<[usize; 4]>::DATA_OFFSET + size_of::<RefCell<ManuallyDrop<[usize; 4]>>>(),
+        size_of::<FlagHeader<[usize; 4]>>()
+    );
+}
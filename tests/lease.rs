@@ -0,0 +1,27 @@
+use flag_cell::*;
+use std::rc::Rc;
+
+#[test]
+fn lease_keeps_payload_alive_after_owner_drop() {
+    let marker = Rc::new(());
+    let cell = FlagCell::new(Rc::clone(&marker));
+    let weak = cell.clone_ref_weak();
+    let lease = cell.lease();
+    assert_eq!(cell.strong_count(), 2);
+    drop(cell);
+    assert_eq!(Rc::strong_count(&marker), 2);
+    assert!(weak.is_alive());
+    drop(lease);
+    assert_eq!(Rc::strong_count(&marker), 1);
+    assert!(!weak.is_alive());
+}
+
+#[test]
+fn lease_blocks_unwrap_and_into_owner() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let lease = cell.lease();
+    drop(cell);
+    assert!(matches!(r.into_owner(), FlagRefOption::Conflict));
+    drop(lease);
+}
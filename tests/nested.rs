@@ -0,0 +1,44 @@
+use flag_cell::*;
+
+#[test]
+fn interleaved_disable_enable_pairs() {
+    let cell = FlagCell::new_nested(1);
+    let r = cell.flag_borrow();
+    // 子系统 A 禁用
+    assert_eq!(cell.disable(), Some(()));
+    // 子系统 B 禁用
+    assert_eq!(cell.disable(), Some(()));
+    assert_eq!(cell.disable_depth(), 2);
+    // A 重新启用，B 仍要求禁用
+    cell.enable();
+    assert_eq!(cell.disable_depth(), 1);
+    assert!(!cell.is_enabled());
+    assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+    // B 重新启用
+    cell.enable();
+    assert_eq!(cell.disable_depth(), 0);
+    assert!(cell.is_enabled());
+    assert_eq!(*r.try_borrow().unwrap(), 1);
+}
+
+#[test]
+fn enable_at_depth_zero_is_noop() {
+    let cell = FlagCell::new_nested(1);
+    assert_eq!(cell.enable(), None);
+    assert_eq!(cell.disable_depth(), 0);
+    cell.disable();
+    assert_eq!(cell.enable(), Some(()));
+    assert_eq!(cell.enable(), None);
+}
+
+#[test]
+fn plain_mode_depth_is_binary() {
+    let cell = FlagCell::new(1);
+    cell.disable();
+    assert_eq!(cell.disable(), None);
+    assert_eq!(cell.disable_depth(), 1);
+    // 非嵌套模式下一次 enable 即覆盖所有 disable
+    cell.enable();
+    assert!(cell.is_enabled());
+    assert_eq!(cell.disable_depth(), 0);
+}
@@ -0,0 +1,59 @@
+use flag_cell::*;
+
+use std::rc::Rc;
+
+#[test]
+fn drop_payload_then_reinit() {
+    let mut cell = FlagCell::new(String::from("first"));
+    let r = cell.flag_borrow();
+    assert!(cell.has_payload());
+    assert!(cell.drop_payload());
+    assert!(!cell.drop_payload());
+    assert!(!cell.has_payload());
+    assert!(!cell.is_enabled());
+    assert!(cell.try_borrow().is_none());
+    assert!(matches!(r.try_borrow(), FlagRefOption::DataDropped));
+    let before = cell.generation();
+    assert!(cell.reinit(String::from("second")).is_ok());
+    assert!(cell.is_enabled());
+    assert!(cell.generation() > before);
+    assert_eq!(*cell.borrow(), "second");
+    assert_eq!(&*r.try_borrow().unwrap(), "second");
+    assert_eq!(cell.reinit(String::from("third")), Err(String::from("third")));
+}
+
+#[test]
+fn drop_payload_keeps_nested_depth_consistent() {
+    let mut cell = FlagCell::new_nested(1);
+    assert!(cell.drop_payload());
+    assert!(!cell.is_enabled());
+    assert_eq!(cell.disable_depth(), 1);
+    cell.reinit(2).unwrap();
+    assert!(cell.is_enabled());
+    assert_eq!(cell.disable_depth(), 0);
+
+    let mut cell = FlagCell::new_nested(1);
+    cell.disable();
+    cell.disable();
+    assert!(cell.drop_payload());
+    assert_eq!(cell.disable_depth(), 2);
+}
+
+#[test]
+fn drop_payload_runs_destructor_once_across_cycles() {
+    let first = Rc::new(());
+    let mut cell = FlagCell::new(first.clone());
+    let r = cell.flag_borrow();
+    assert!(cell.drop_payload());
+    assert_eq!(Rc::strong_count(&first), 1);
+    for _ in 0..3 {
+        let next = Rc::new(());
+        cell.reinit(next.clone()).unwrap();
+        assert!(matches!(r.try_borrow(), FlagRefOption::Some(_)));
+        assert!(cell.drop_payload());
+        assert_eq!(Rc::strong_count(&next), 1);
+    }
+    // 空壳状态下销毁不会再次析构
+    drop(cell);
+    assert!(matches!(r.try_borrow(), FlagRefOption::DataDropped));
+}
@@ -0,0 +1,36 @@
+use flag_cell::*;
+
+use std::cell::RefCell;
+
+fn push_twice(cell: &RefCell<Vec<i32>>, v: i32) {
+    cell.borrow_mut().push(v);
+    cell.borrow_mut().push(v);
+}
+
+#[test]
+fn plain_refcell_shares_borrow_state() {
+    let cell = FlagCell::new(vec![1]);
+    let plain = cell.as_plain_refcell();
+    push_twice(plain, 2);
+    assert_eq!(*cell.borrow(), [1, 2, 2]);
+    
+    let guard = plain.borrow();
+    assert!(cell.try_borrow_mut().is_none());
+    assert!(cell.try_borrow().is_some());
+    drop(guard);
+    let guard = cell.borrow_mut();
+    assert!(plain.try_borrow().is_err());
+    drop(guard);
+}
+
+#[test]
+fn plain_refcell_replace_hands_out_old_value() {
+    let cell = FlagCell::new(String::from("old"));
+    let r = cell.flag_borrow();
+    let old = cell.as_plain_refcell().replace(String::from("new"));
+    assert_eq!(old, "old");
+    assert_eq!(&*r.try_borrow().unwrap(), "new");
+    // 新值仍由 FlagCell 析构
+    drop(cell);
+    drop(r);
+}
@@ -0,0 +1,85 @@
+use flag_cell::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[test]
+fn panic_in_guarded_borrow_poisons() {
+    let cell = FlagCell::new_poisoning(1);
+    let r = catch_unwind(AssertUnwindSafe(|| {
+        let mut g = cell.borrow_mut_guarded();
+        *g = 2;
+        panic!("mid-mutation");
+    }));
+    assert!(r.is_err());
+    assert!(cell.is_poisoned());
+    assert!(cell.try_borrow().is_none());
+    assert!(catch_unwind(AssertUnwindSafe(|| drop(cell.borrow()))).is_err());
+    cell.clear_poison();
+    assert_eq!(*cell.borrow(), 2);
+}
+
+#[test]
+fn panic_in_scope_poisons() {
+    let cell = FlagCell::new_poisoning(1);
+    let r = catch_unwind(AssertUnwindSafe(|| cell.scope(|_| panic!("mid-mutation"))));
+    assert!(r.is_err());
+    assert!(cell.is_poisoned());
+    assert!(cell.is_enabled());
+}
+
+#[test]
+fn poisoned_cell_rejects_scope() {
+    let cell = FlagCell::new_poisoning(1);
+    let _ = catch_unwind(AssertUnwindSafe(|| cell.atomic_update(|_| panic!("mid-mutation"))));
+    assert!(cell.is_poisoned());
+    let r = catch_unwind(AssertUnwindSafe(|| cell.scope(|v| *v = 99)));
+    assert!(r.is_err());
+    cell.clear_poison();
+    assert_eq!(*cell.borrow(), 1);
+}
+
+#[test]
+fn borrow_or_resurrect_rejects_poisoned() {
+    let cell = FlagCell::new_poisoning(1);
+    let r = cell.flag_borrow();
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        let _g = cell.borrow_mut_guarded();
+        panic!("mid-mutation");
+    }));
+    drop(cell);
+    assert!(matches!(r.borrow_or_resurrect(), FlagRefOption::Conflict));
+    assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+}
+
+#[test]
+fn plain_borrow_mut_does_not_poison() {
+    let cell = FlagCell::new_poisoning(1);
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        let _g = cell.borrow_mut();
+        panic!("mid-mutation");
+    }));
+    assert!(!cell.is_poisoned());
+}
+
+#[test]
+fn refs_observe_poison() {
+    let cell = FlagCell::new_poisoning(1);
+    let r = cell.flag_borrow();
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        let _g = cell.borrow_mut_guarded();
+        panic!("mid-mutation");
+    }));
+    assert_eq!(r.try_borrow_detailed().err(), Some(FlagBorrowError::Poisoned));
+    assert_eq!(r.try_borrow_mut_detailed().err(), Some(FlagBorrowError::Poisoned));
+    cell.clear_poison();
+    assert!(r.try_borrow_detailed().is_ok());
+}
+
+#[test]
+fn non_poisoning_cell_ignores_guarded_panic() {
+    let cell = FlagCell::new(1);
+    let _ = catch_unwind(AssertUnwindSafe(|| {
+        let _g = cell.borrow_mut_guarded();
+        panic!("mid-mutation");
+    }));
+    assert!(!cell.is_poisoned());
+}
@@ -0,0 +1,39 @@
+use flag_cell::*;
+
+struct Player {
+    name: String,
+    hp: u32,
+}
+
+#[test]
+fn projection_reads_a_field() {
+    let cell = FlagCell::new(Player { name: String::from("a"), hp: 10 });
+    let hp = cell.flag_borrow().project(|p| &p.hp);
+    let name = cell.flag_borrow().project(|p| p.name.as_str());
+    assert_eq!(*hp.try_borrow().unwrap(), 10);
+    assert_eq!(&*name.try_borrow().unwrap(), "a");
+    cell.borrow_mut().hp = 3;
+    assert_eq!(*hp.clone().try_borrow().unwrap(), 3);
+    // 投影持有一个 FlagRef
+    assert_eq!(cell.ref_count(), 2);
+}
+
+#[test]
+fn parent_disable_disables_projection() {
+    let cell = FlagCell::new(Player { name: String::new(), hp: 10 });
+    let hp = cell.flag_borrow().project(|p| &p.hp);
+    cell.disable();
+    assert!(!hp.is_enabled());
+    assert!(matches!(hp.try_borrow(), FlagRefOption::Disabled));
+    assert_eq!(hp.try_borrow_detailed().err(), Some(FlagBorrowError::Disabled));
+    cell.enable();
+    assert_eq!(*hp.try_borrow().unwrap(), 10);
+}
+
+#[test]
+fn projection_conflicts_with_parent_mut_borrow() {
+    let cell = FlagCell::new(Player { name: String::new(), hp: 10 });
+    let hp = cell.flag_borrow().project(|p| &p.hp);
+    let _g = cell.borrow_mut();
+    assert!(matches!(hp.try_borrow(), FlagRefOption::Conflict));
+}
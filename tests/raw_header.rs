@@ -0,0 +1,74 @@
+use flag_cell::*;
+use std::alloc::{alloc, dealloc, Layout};
+use std::cell::Cell;
+use std::ptr::NonNull;
+
+thread_local! {
+    static DEALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn counting_dealloc<T>(ptr: NonNull<FlagHeader<T>>) {
+    DEALLOCS.with(|c| c.set(c.get() + 1));
+    // SAFETY: 内存由 alloc_header 以相同布局分配
+    unsafe { dealloc(ptr.as_ptr().cast(), Layout::new::<FlagHeader<T>>()) }
+}
+
+fn alloc_header<T>(value: T) -> NonNull<FlagHeader<T>> {
+    let layout = Layout::new::<FlagHeader<T>>();
+    // SAFETY: 布局大小非零
+    let raw = unsafe { alloc(layout) }.cast::<FlagHeader<T>>();
+    let ptr = NonNull::new(raw).expect("allocation failed");
+    // SAFETY: 内存有效且对齐
+    unsafe { ptr.as_ptr().write(FlagHeader::new(value)) };
+    ptr
+}
+
+#[test]
+fn custom_dealloc_runs_once_after_last_holder() {
+    DEALLOCS.with(|c| c.set(0));
+    // SAFETY: 核心结构由 FlagHeader::new 初始化，且只交给这一个 FlagCell
+    let cell = unsafe { FlagCell::from_raw_header(alloc_header(String::from("raw")), counting_dealloc) };
+    let r = cell.flag_borrow();
+    let weak = cell.clone_ref_weak();
+    assert_eq!(&*r.try_borrow().unwrap(), "raw");
+    drop(cell);
+    drop(r);
+    assert_eq!(DEALLOCS.with(Cell::get), 0);
+    drop(weak);
+    assert_eq!(DEALLOCS.with(Cell::get), 1);
+}
+
+#[test]
+fn custom_dealloc_after_unwrap() {
+    DEALLOCS.with(|c| c.set(0));
+    // SAFETY: 同上
+    let cell = unsafe { FlagCell::from_raw_header(alloc_header(vec![1u8, 2]), counting_dealloc) };
+    assert_eq!(cell.unwrap(), [1, 2]);
+    assert_eq!(DEALLOCS.with(Cell::get), 1);
+}
+
+#[test]
+fn ordinary_cells_do_not_use_custom_dealloc() {
+    DEALLOCS.with(|c| c.set(0));
+    drop(FlagCell::new(1));
+    assert_eq!(DEALLOCS.with(Cell::get), 0);
+}
+
+#[test]
+fn data_offset_locates_the_payload() {
+    let header = alloc_header(0xABCD_u32);
+    // SAFETY: 同上
+    let cell = unsafe { FlagCell::from_raw_header(header, counting_dealloc) };
+    let data = (&*cell as *const std::cell::RefCell<_>).cast::<u8>();
+    // SAFETY: 两个指针位于同一分配内
+    let offset = unsafe { data.offset_from(header.as_ptr().cast::<u8>()) };
+    assert_eq!(offset as usize, FlagHeader::<u32>::DATA_OFFSET);
+    assert_eq!(*cell.borrow(), 0xABCD);
+}
+
+#[test]
+fn data_offset_does_not_depend_on_small_payloads() {
+    assert_eq!(FlagHeader::<u8>::DATA_OFFSET, FlagHeader::<u32>::DATA_OFFSET);
+    assert_eq!(FlagHeader::<()>::DATA_OFFSET, FlagHeader::<usize>::DATA_OFFSET);
+    assert!(FlagHeader::<u32>::DATA_OFFSET < std::mem::size_of::<FlagHeader<u32>>());
+}
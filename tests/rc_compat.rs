@@ -0,0 +1,58 @@
+use flag_cell::*;
+
+#[test]
+fn downgrade_is_flag_borrow() {
+    let cell = FlagCell::new(1);
+    let r = cell.downgrade();
+    assert_eq!(cell.ref_count(), 1);
+    assert_eq!(cell.strong_count(), 2);
+    assert!(r.to_handle().same_logical_object(&cell));
+}
+
+#[test]
+fn strong_count_includes_owner_refs_and_leases() {
+    let cell = FlagCell::new(1);
+    assert_eq!(cell.strong_count(), 1);
+    let r = cell.downgrade();
+    let lease = cell.lease();
+    assert_eq!(cell.strong_count(), 3);
+    assert_eq!(r.strong_count(), 3);
+    drop(lease);
+    drop(cell);
+    assert_eq!(r.strong_count(), 1);
+}
+
+#[test]
+fn upgrade_is_resurrect() {
+    let cell = FlagCell::new(String::from("a"));
+    let r = cell.downgrade();
+    // 与 resurrect 相同：仍启用时返回 Disabled，持有者存活但被禁用时返回 Conflict
+    assert!(matches!(r.upgrade(), FlagRefOption::Disabled));
+    cell.disable();
+    assert!(matches!(r.upgrade(), FlagRefOption::Conflict));
+    drop(cell);
+    let FlagRefOption::Some(owner) = r.upgrade() else {
+        panic!("upgrade failed");
+    };
+    assert_eq!(&*owner.borrow(), "a");
+    assert_eq!(owner.strong_count(), 2);
+    assert!(matches!(r.upgrade(), FlagRefOption::Disabled));
+}
+
+#[test]
+fn weak_count_counts_only_weak_refs() {
+    let cell = FlagCell::new(1);
+    let r = cell.downgrade();
+    assert_eq!(cell.weak_count(), 0);
+    assert_eq!(r.weak_count(), 0);
+    let w1 = cell.clone_ref_weak();
+    let w2 = cell.clone_ref_weak();
+    assert_eq!(cell.weak_count(), 2);
+    assert_eq!(r.weak_count(), 2);
+    drop(w1);
+    drop(cell);
+    assert_eq!(r.weak_count(), 1);
+    drop(w2);
+    assert_eq!(r.weak_count(), 0);
+    assert_eq!(FlagRef::<i32>::new().weak_count(), 0);
+}
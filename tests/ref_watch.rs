@@ -0,0 +1,39 @@
+use flag_cell::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[test]
+fn callback_fires_past_threshold() {
+    let cell = FlagCell::new(1);
+    let fired = Rc::new(Cell::new(Vec::new()));
+    let log = Rc::clone(&fired);
+    // 持有者总数由 3 增长到 4 时触发
+    cell.warn_on_ref_count_over(3, move |n| {
+        let mut v = log.take();
+        v.push(n);
+        log.set(v);
+    });
+    let a = cell.flag_borrow();
+    let _b = cell.flag_borrow();
+    assert!(fired.take().is_empty());
+    let c = cell.flag_borrow();
+    assert_eq!(fired.take(), [4]);
+    let d = cell.flag_borrow();
+    assert!(fired.take().is_empty());
+    // 回落后再次越过阈值会再次触发
+    drop(c);
+    drop(d);
+    let _e = a.clone();
+    assert_eq!(fired.take(), [4]);
+}
+
+#[test]
+fn replacing_the_callback() {
+    let cell = FlagCell::new(1);
+    let count = Rc::new(Cell::new(0));
+    cell.warn_on_ref_count_over(1, |_| panic!("replaced"));
+    let c = Rc::clone(&count);
+    cell.warn_on_ref_count_over(1, move |_| c.set(c.get() + 1));
+    let _r = cell.flag_borrow();
+    assert_eq!(count.get(), 1);
+}
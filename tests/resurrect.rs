@@ -0,0 +1,158 @@
+use flag_cell::*;
+
+#[test]
+fn borrow_or_resurrect_enabled_path() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    match r.borrow_or_resurrect() {
+        FlagRefOption::Some((owner, b)) => {
+            assert!(owner.is_none());
+            assert_eq!(*b, 1);
+        }
+        other => panic!("unexpected {other:?}"),
+    }
+    assert_eq!(cell.ref_count(), 1);
+}
+
+#[test]
+fn borrow_or_resurrect_resurrect_path() {
+    let cell = FlagCell::new(String::from("a"));
+    let r = cell.flag_borrow();
+    drop(cell);
+    let owner = match r.borrow_or_resurrect() {
+        FlagRefOption::Some((Some(owner), b)) => {
+            assert_eq!(&*b, "a");
+            owner
+        }
+        other => panic!("unexpected {other:?}"),
+    };
+    assert!(r.is_enabled());
+    assert_eq!(owner.ref_count(), 1);
+    drop(owner);
+    assert!(!r.is_enabled());
+}
+
+#[test]
+fn borrow_or_resurrect_ref_outlives_owner() {
+    let cell = FlagCell::new(2);
+    let r = cell.flag_borrow();
+    drop(cell);
+    if let FlagRefOption::Some((owner, b)) = r.borrow_or_resurrect() {
+        drop(owner);
+        // Ref 借用自 r，内存仍然有效
+        assert_eq!(*b, 2);
+    } else {
+        panic!("resurrect failed");
+    }
+}
+
+#[test]
+fn borrow_or_resurrect_with_live_owner_is_conflict() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    cell.disable();
+    assert!(matches!(r.borrow_or_resurrect(), FlagRefOption::Conflict));
+}
+
+#[test]
+fn detached_ref_is_disabled_until_resurrected() {
+    let r = FlagRef::new_detached(String::from("d"));
+    assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+    assert_eq!(r.ref_count(), 1);
+    let cell = r.resurrect().unwrap();
+    assert!(cell.is_enabled());
+    assert_eq!(cell.ref_count(), 1);
+    assert_eq!(&*r.try_borrow().unwrap(), "d");
+    drop(r);
+    assert_eq!(cell.try_unwrap().ok().as_deref(), Some("d"));
+}
+
+#[test]
+fn detached_ref_drops_payload_with_last_ref() {
+    use std::rc::Rc;
+    let marker = Rc::new(());
+    let r = FlagRef::new_detached(Rc::clone(&marker));
+    let r2 = r.clone();
+    drop(r);
+    assert_eq!(Rc::strong_count(&marker), 2);
+    drop(r2);
+    assert_eq!(Rc::strong_count(&marker), 1);
+}
+
+#[test]
+fn into_owner_sole_ref() {
+    let cell = FlagCell::new(String::from("a"));
+    let r = cell.flag_borrow();
+    let weak = cell.clone_ref_weak();
+    drop(cell);
+    let owner = r.into_owner().unwrap();
+    assert!(owner.is_enabled());
+    assert_eq!(owner.ref_count(), 0);
+    assert_eq!(owner.generation(), 1);
+    assert!(weak.is_alive());
+    assert_eq!(owner.try_unwrap().ok().as_deref(), Some("a"));
+}
+
+#[test]
+fn into_owner_shared_ref_is_conflict() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let other = r.clone();
+    drop(cell);
+    // 失败时 r 被消费
+    assert!(matches!(r.into_owner(), FlagRefOption::Conflict));
+    assert_eq!(other.ref_count(), 1);
+    assert!(matches!(other.into_owner(), FlagRefOption::Some(_)));
+}
+
+#[test]
+fn into_owner_with_live_cell_is_conflict() {
+    let cell = FlagCell::new(1);
+    assert!(matches!(cell.flag_borrow().into_owner(), FlagRefOption::Conflict));
+    assert_eq!(cell.ref_count(), 0);
+    assert!(matches!(FlagRef::<i32>::new().into_owner(), FlagRefOption::Empty));
+}
+
+#[test]
+fn counts_across_resurrect_cycles() {
+    use std::rc::Rc;
+    let marker = Rc::new(());
+    let cell = FlagCell::new(Rc::clone(&marker));
+    let refs: Vec<_> = (0..3).map(|_| cell.flag_borrow()).collect();
+    assert_eq!((cell.ref_count(), cell.strong_count()), (3, 4));
+    drop(cell);
+    assert_eq!((refs[0].ref_count(), refs[0].strong_count()), (3, 3));
+
+    let mut refs = refs;
+    let cell = refs[0].resurrect().unwrap();
+    assert_eq!((cell.ref_count(), cell.strong_count()), (3, 4));
+    assert_eq!(refs[1].ref_count(), 3);
+    refs.pop();
+    assert_eq!((cell.ref_count(), cell.strong_count()), (2, 3));
+    drop(cell);
+    assert_eq!((refs[0].ref_count(), refs[0].strong_count()), (2, 2));
+
+    let cell = refs[1].resurrect().unwrap();
+    assert_eq!(cell.generation(), 2);
+    assert_eq!((cell.ref_count(), cell.strong_count()), (2, 3));
+    drop(refs);
+    assert_eq!((cell.ref_count(), cell.strong_count()), (0, 1));
+    assert_eq!(Rc::strong_count(&marker), 2);
+    drop(cell);
+    assert_eq!(Rc::strong_count(&marker), 1);
+}
+
+#[test]
+fn last_ref_after_resurrected_cell_dropped() {
+    use std::rc::Rc;
+    let marker = Rc::new(());
+    let cell = FlagCell::new(Rc::clone(&marker));
+    let r = cell.flag_borrow();
+    drop(cell);
+    drop(r.resurrect().unwrap());
+    drop(r.resurrect().unwrap());
+    assert_eq!(r.ref_count(), 1);
+    assert_eq!(r.generation(), Some(2));
+    drop(r);
+    assert_eq!(Rc::strong_count(&marker), 1);
+}
@@ -0,0 +1,33 @@
+use flag_cell::*;
+
+#[test]
+fn retain_live_mixed_handles() {
+    let live = FlagCell::new(1);
+    let disabled = FlagCell::new(2);
+    let dropped = FlagCell::new(3);
+    let mut v = vec![
+        live.flag_borrow(),
+        disabled.flag_borrow(),
+        dropped.flag_borrow(),
+        FlagRef::new(),
+        live.flag_borrow(),
+    ];
+    disabled.disable();
+    drop(dropped);
+    retain_live(&mut v);
+    assert_eq!(v.len(), 2);
+    assert!(v.iter().all(|r| *r.try_borrow().unwrap() == 1));
+}
+
+#[test]
+fn retain_live_keeps_all_live() {
+    let a = FlagCell::new("a");
+    let b = FlagCell::new("b");
+    let mut v = vec![a.flag_borrow(), b.flag_borrow()];
+    retain_live(&mut v);
+    assert_eq!(v.len(), 2);
+    drop(a);
+    drop(b);
+    retain_live(&mut v);
+    assert!(v.is_empty());
+}
@@ -0,0 +1,97 @@
+use flag_cell::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[test]
+fn observers_see_disabled_during_scope() {
+    let cell = FlagCell::new(vec![1]);
+    let r = cell.flag_borrow();
+    let len = cell.scope(|v| {
+        assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+        v.push(2);
+        v.push(3);
+        v.len()
+    });
+    assert_eq!(len, 3);
+    assert!(cell.is_enabled());
+    assert_eq!(*r.try_borrow().unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn scope_reenables_on_unwind() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        cell.scope(|v| {
+            *v = 2;
+            panic!("mid-scope");
+        })
+    }));
+    assert!(res.is_err());
+    assert!(cell.is_enabled());
+    assert_eq!(*r.try_borrow().unwrap(), 2);
+}
+
+#[test]
+#[should_panic]
+fn scope_panics_while_borrowed() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow();
+    cell.scope(|v| *v = 2);
+}
+
+#[test]
+fn scope_refuses_leased_cell() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let FlagRefOption::Some(lease) = r.upgrade_strong() else {
+        panic!("lease failed");
+    };
+    let res = catch_unwind(AssertUnwindSafe(|| cell.scope(|v| *v = 2)));
+    assert!(res.is_err());
+    // f 未执行，观察者看到的状态未变
+    assert!(cell.is_enabled());
+    assert_eq!(*lease.try_borrow().unwrap(), 1);
+}
+
+#[test]
+fn observer_polling_during_atomic_update() {
+    let cell = FlagCell::new((0, 0));
+    let observer = cell.flag_borrow();
+    let mut seen = Vec::new();
+    let mut poll = || match observer.try_borrow() {
+        FlagRefOption::Some(v) => seen.push(Some(*v)),
+        FlagRefOption::Disabled => seen.push(None),
+        other => panic!("unexpected {other:?}"),
+    };
+    poll();
+    cell.atomic_update(|v| {
+        v.0 = 1;
+        poll();
+        v.1 = 1;
+        poll();
+    });
+    poll();
+    // 只会看到旧值、Disabled 或新值，不会看到 (1, 0)
+    assert_eq!(seen, [Some((0, 0)), None, None, Some((1, 1))]);
+    assert_eq!(cell.ref_count(), 1);
+}
+
+#[test]
+fn atomic_update_preserves_disabled_state() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    cell.disable();
+    cell.atomic_update(|v| *v = 2);
+    assert!(!cell.is_enabled());
+    assert_eq!(r.ref_count(), 1);
+    cell.enable();
+    assert_eq!(*r.try_borrow().unwrap(), 2);
+}
+
+#[test]
+fn atomic_update_restores_on_unwind() {
+    let cell = FlagCell::new(1);
+    let res = catch_unwind(AssertUnwindSafe(|| cell.atomic_update(|_| panic!("mid-update"))));
+    assert!(res.is_err());
+    assert!(cell.is_enabled());
+}
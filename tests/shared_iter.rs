@@ -0,0 +1,51 @@
+use flag_cell::*;
+
+#[test]
+fn two_handles_advance_a_shared_range() {
+    let cell = FlagCell::new(0..5);
+    let a = cell.flag_borrow();
+    let b = cell.flag_borrow();
+    let mut seen = Vec::new();
+    for step in 0.. {
+        let h = if step % 2 == 0 { &a } else { &b };
+        match h.next() {
+            FlagRefOption::Some(v) => seen.push(v),
+            FlagRefOption::Empty => break,
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+    assert_eq!(seen, [0, 1, 2, 3, 4]);
+    assert!(matches!(cell.next(), FlagRefOption::Empty));
+}
+
+#[test]
+fn cell_next_interleaves_with_refs() {
+    let cell = FlagCell::new(0..3);
+    let r = cell.flag_borrow();
+    assert!(matches!(cell.next(), FlagRefOption::Some(0)));
+    assert!(matches!(r.next(), FlagRefOption::Some(1)));
+    assert!(matches!(cell.next(), FlagRefOption::Some(2)));
+}
+
+#[test]
+fn shared_iter_failure_variants() {
+    let cell = FlagCell::new(0..3);
+    let r = cell.flag_borrow();
+    {
+        let _g = cell.borrow();
+        assert!(matches!(cell.next(), FlagRefOption::Conflict));
+        assert!(matches!(r.next(), FlagRefOption::Conflict));
+    }
+    cell.disable();
+    assert!(matches!(cell.next(), FlagRefOption::Disabled));
+    assert!(matches!(r.next(), FlagRefOption::Disabled));
+}
+
+#[test]
+fn next_on_dropped_payload() {
+    let mut cell = FlagCell::new(0..3);
+    let r = cell.flag_borrow();
+    assert!(cell.drop_payload());
+    assert!(matches!(cell.next(), FlagRefOption::DataDropped));
+    assert!(matches!(r.next(), FlagRefOption::DataDropped));
+}
@@ -0,0 +1,87 @@
+use flag_cell::*;
+
+#[test]
+fn cell_snapshot_matches_queries() {
+    let cell = FlagCell::new(1);
+    let _a = cell.flag_borrow();
+    let _b = cell.flag_borrow();
+    let snap = cell.snapshot();
+    assert_eq!(snap.ref_count, cell.ref_count());
+    assert_eq!(snap.enabled, cell.is_enabled());
+    assert_eq!(snap.generation, cell.generation());
+    cell.disable();
+    let snap = cell.snapshot();
+    assert_eq!((snap.ref_count, snap.enabled), (2, false));
+}
+
+#[test]
+fn ref_snapshot_matches_queries() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let _other = r.clone();
+    let snap = r.snapshot().unwrap();
+    assert_eq!(snap.ref_count, r.ref_count());
+    assert_eq!(snap.enabled, r.is_enabled());
+    assert_eq!(Some(snap.generation), r.generation());
+    drop(cell);
+    let snap = r.snapshot().unwrap();
+    assert_eq!(snap.ref_count, r.ref_count());
+    assert!(!snap.enabled);
+}
+
+#[test]
+fn ref_snapshot_empty() {
+    assert_eq!(FlagRef::<i32>::new().snapshot(), None);
+}
+
+#[test]
+fn snapshot_value_is_detached() {
+    let cell = FlagCell::new(vec![1]);
+    let snap = cell.snapshot_value().unwrap();
+    cell.borrow_mut().push(2);
+    assert_eq!(snap, [1]);
+    assert_eq!(*cell.borrow(), [1, 2]);
+}
+
+#[test]
+fn snapshot_value_none_while_mutably_borrowed() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow_mut();
+    assert_eq!(cell.snapshot_value(), None);
+}
+
+#[test]
+fn clone_into_reuses_capacity() {
+    let cell = FlagCell::new(vec![1, 2, 3]);
+    let mut dst = Vec::with_capacity(16);
+    let ptr = dst.as_ptr();
+    assert!(cell.clone_into(&mut dst));
+    assert_eq!(dst, [1, 2, 3]);
+    assert_eq!(dst.as_ptr(), ptr);
+    assert!(dst.capacity() >= 16);
+}
+
+#[test]
+fn clone_into_fails_while_mutably_borrowed() {
+    let cell = FlagCell::new(vec![1]);
+    let mut dst = vec![9];
+    let _g = cell.borrow_mut();
+    assert!(!cell.clone_into(&mut dst));
+    assert_eq!(dst, [9]);
+}
+
+#[test]
+fn raw_count_sign_and_magnitude() {
+    let cell = FlagCell::new(1);
+    assert_eq!(cell.raw_count(), 1);
+    let r = cell.flag_borrow();
+    let _r2 = r.clone();
+    assert_eq!(cell.raw_count(), 3);
+    assert_eq!(r.raw_count(), Some(3));
+    cell.disable();
+    assert_eq!(cell.raw_count(), -3);
+    assert_eq!(r.raw_count(), Some(-3));
+    cell.enable();
+    assert_eq!(cell.raw_count(), 3);
+    assert_eq!(FlagRef::<i32>::new().raw_count(), None);
+}
@@ -0,0 +1,142 @@
+use flag_cell::*;
+
+struct Pair {
+    left: Vec<i32>,
+    right: String,
+}
+
+#[test]
+fn borrow_mut_split_mutates_both_fields() {
+    let cell = FlagCell::new(Pair { left: vec![], right: String::new() });
+    {
+        let (mut l, mut r) = cell.borrow_mut_split(|p| (&mut p.left, &mut p.right));
+        l.push(1);
+        r.push('x');
+        l.push(2);
+        r.push('y');
+    }
+    let p = cell.borrow();
+    assert_eq!(p.left, [1, 2]);
+    assert_eq!(p.right, "xy");
+}
+
+#[test]
+fn borrow_mut_split_holds_borrow_until_both_dropped() {
+    let cell = FlagCell::new(Pair { left: vec![], right: String::new() });
+    let (l, r) = cell.borrow_mut_split(|p| (&mut p.left, &mut p.right));
+    drop(l);
+    assert!(cell.try_borrow().is_none());
+    drop(r);
+    assert!(cell.try_borrow().is_some());
+}
+
+#[test]
+#[should_panic]
+fn borrow_mut_split_panics_while_borrowed() {
+    let cell = FlagCell::new(Pair { left: vec![], right: String::new() });
+    let _g = cell.borrow();
+    let _ = cell.borrow_mut_split(|p| (&mut p.left, &mut p.right));
+}
+
+enum Shape {
+    Circle(f64),
+    Square(u32),
+}
+
+#[test]
+fn filter_map_matching_variant() {
+    let cell = FlagCell::new(Shape::Circle(1.5));
+    let r = cell.borrow_filter_map(|s| match s {
+        Shape::Circle(r) => Some(r),
+        _ => None,
+    });
+    assert_eq!(r.as_deref(), Some(&1.5));
+    drop(r);
+    if let Some(mut r) = cell.borrow_mut_filter_map(|s| match s {
+        Shape::Circle(r) => Some(r),
+        _ => None,
+    }) {
+        *r = 2.0;
+    }
+    assert!(matches!(*cell.borrow(), Shape::Circle(r) if r == 2.0));
+}
+
+#[test]
+fn filter_map_non_matching_releases_borrow() {
+    let cell = FlagCell::new(Shape::Square(3));
+    assert!(cell.borrow_filter_map(|s| match s {
+        Shape::Circle(r) => Some(r),
+        _ => None,
+    }).is_none());
+    assert!(cell.borrow_mut_filter_map(|s| match s {
+        Shape::Circle(r) => Some(r),
+        _ => None,
+    }).is_none());
+    // 借用已释放
+    *cell.borrow_mut() = Shape::Square(4);
+    assert!(matches!(*cell.borrow(), Shape::Square(4)));
+}
+
+struct Triple {
+    a: u32,
+    b: String,
+    c: Vec<u8>,
+}
+
+#[test]
+fn borrow_mut_split3_mutates_three_fields() {
+    let cell = FlagCell::new(Triple { a: 0, b: String::new(), c: Vec::new() });
+    {
+        let (mut a, mut b, mut c) = cell.borrow_mut_split3(|t| (&mut t.a, &mut t.b, &mut t.c));
+        *a += 1;
+        b.push('x');
+        c.push(7);
+        *a += 1;
+    }
+    let t = cell.borrow();
+    assert_eq!((t.a, t.b.as_str(), t.c.as_slice()), (2, "x", &[7u8][..]));
+}
+
+#[test]
+fn borrow_mut_split3_holds_borrow_until_all_dropped() {
+    let cell = FlagCell::new(Triple { a: 0, b: String::new(), c: Vec::new() });
+    let (a, b, c) = cell.borrow_mut_split3(|t| (&mut t.a, &mut t.b, &mut t.c));
+    drop(a);
+    drop(c);
+    assert!(cell.try_borrow().is_none());
+    drop(b);
+    assert!(cell.try_borrow().is_some());
+}
+
+#[test]
+fn borrow_ok_and_err() {
+    let ok: FlagCell<Result<u32, String>> = FlagCell::new(Ok(3));
+    assert_eq!(ok.borrow_ok().as_deref(), Some(&3));
+    assert!(ok.borrow_err().is_none());
+    let err: FlagCell<Result<u32, String>> = FlagCell::new(Err(String::from("bad")));
+    assert!(err.borrow_ok().is_none());
+    assert_eq!(err.borrow_err().as_deref().map(String::as_str), Some("bad"));
+    // 投影不匹配时借用立即释放
+    *err.borrow_mut() = Ok(1);
+}
+
+#[test]
+fn borrow_tuple_mut_mutates_both_elements() {
+    let cell = FlagCell::new((1, String::from("a")));
+    let (mut n, mut s) = cell.borrow_tuple_mut();
+    *n += 1;
+    s.push('b');
+    assert!(cell.try_borrow().is_none());
+    drop(n);
+    assert!(cell.try_borrow().is_none());
+    drop(s);
+    assert_eq!(*cell.borrow(), (2, String::from("ab")));
+}
+
+#[test]
+#[should_panic]
+fn borrow_tuple_mut_panics_while_borrowed() {
+    let cell = FlagCell::new((1, 2));
+    let _g = cell.borrow();
+    let _ = cell.borrow_tuple_mut();
+}
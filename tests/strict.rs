@@ -0,0 +1,27 @@
+use flag_cell::*;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[test]
+fn strict_disabled_borrow_panics() {
+    let cell = FlagCell::new_strict(1);
+    cell.disable();
+    let err = catch_unwind(AssertUnwindSafe(|| drop(cell.borrow()))).unwrap_err();
+    let msg = err.downcast_ref::<String>().map(String::as_str)
+        .or_else(|| err.downcast_ref::<&str>().copied())
+        .unwrap();
+    assert!(msg.contains("borrow of disabled FlagCell"), "{msg}");
+    assert!(catch_unwind(AssertUnwindSafe(|| drop(cell.borrow_mut()))).is_err());
+    assert!(cell.try_borrow().is_none());
+    assert!(cell.try_borrow_mut().is_none());
+    cell.enable();
+    assert_eq!(*cell.borrow(), 1);
+}
+
+#[test]
+fn lenient_disabled_borrow_reads() {
+    let cell = FlagCell::new(1);
+    cell.disable();
+    assert_eq!(*cell.borrow(), 1);
+    *cell.borrow_mut() = 2;
+    assert_eq!(cell.try_borrow().as_deref(), Some(&2));
+}
@@ -0,0 +1,41 @@
+use flag_cell::*;
+
+#[test]
+fn disable_fails_while_leased() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let lease = match r.upgrade_strong() {
+        FlagRefOption::Some(lease) => lease,
+        other => panic!("unexpected {other:?}"),
+    };
+    assert_eq!(cell.disable(), None);
+    // SAFETY: 仅测试租约对禁用的阻止
+    assert!(matches!(unsafe { r.disable() }, FlagRefOption::Conflict));
+    assert!(cell.is_enabled());
+    assert_eq!(*lease.try_borrow().unwrap(), 1);
+    drop(lease);
+    assert_eq!(cell.disable(), Some(()));
+    assert!(!cell.is_enabled());
+}
+
+#[test]
+fn nested_leases_release_in_any_order() {
+    let cell = FlagCell::new(1);
+    let a = cell.flag_borrow();
+    let b = cell.flag_borrow();
+    let la = a.upgrade_strong().unwrap();
+    let lb = b.upgrade_strong().unwrap();
+    drop(la);
+    assert_eq!(cell.disable(), None);
+    drop(lb);
+    assert_eq!(cell.disable(), Some(()));
+}
+
+#[test]
+fn upgrade_strong_on_disabled_or_empty() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    cell.disable();
+    assert!(matches!(r.upgrade_strong(), FlagRefOption::Disabled));
+    assert!(matches!(FlagRef::<i32>::new().upgrade_strong(), FlagRefOption::Empty));
+}
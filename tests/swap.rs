@@ -0,0 +1,62 @@
+use flag_cell::*;
+
+#[test]
+fn swap_with_exchanges_payloads() {
+    let a = FlagCell::new(String::from("a"));
+    let b = FlagCell::new(String::from("b"));
+    let rb = b.flag_borrow();
+    assert!(matches!(a.swap_with(&rb), FlagRefOption::Some(())));
+    assert_eq!(&*a.borrow(), "b");
+    assert_eq!(&*b.borrow(), "a");
+    assert_eq!(&*rb.try_borrow().unwrap(), "a");
+}
+
+#[test]
+fn swap_with_same_allocation_is_noop() {
+    let a = FlagCell::new(1);
+    let ra = a.flag_borrow();
+    assert!(matches!(a.swap_with(&ra), FlagRefOption::Some(())));
+    assert_eq!(*a.borrow(), 1);
+}
+
+#[test]
+fn swap_with_failure_variants() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new(2);
+    let rb = b.flag_borrow();
+    assert!(matches!(a.swap_with(&FlagRef::new()), FlagRefOption::Empty));
+    {
+        let _g = b.borrow();
+        assert!(matches!(a.swap_with(&rb), FlagRefOption::Conflict));
+    }
+    {
+        let _g = a.borrow();
+        assert!(matches!(a.swap_with(&rb), FlagRefOption::Conflict));
+    }
+    b.disable();
+    assert!(matches!(a.swap_with(&rb), FlagRefOption::Disabled));
+    assert_eq!((*a.borrow(), *b.borrow()), (1, 2));
+}
+
+#[test]
+fn with_two_mut_different_types() {
+    let names = FlagCell::new(vec![String::from("a")]);
+    let total = FlagCell::new(0usize);
+    let len = with_two_mut(&names, &total, |names, total| {
+        names.push(String::from("b"));
+        *total += names.len();
+        names.len()
+    });
+    assert_eq!(len, 2);
+    assert_eq!(*total.borrow(), 2);
+    assert_eq!(names.borrow().len(), 2);
+}
+
+#[test]
+#[should_panic]
+fn with_two_mut_panics_if_borrowed() {
+    let a = FlagCell::new(1);
+    let b = FlagCell::new('b');
+    let _g = b.borrow();
+    with_two_mut(&a, &b, |_, _| ());
+}
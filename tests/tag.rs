@@ -0,0 +1,31 @@
+use flag_cell::*;
+
+#[test]
+fn tag_set_on_cell_read_through_ref() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    assert_eq!(r.tag(), Some(0));
+    cell.set_tag(0xABCD);
+    assert_eq!(r.tag(), Some(0xABCD));
+    assert_eq!(r.clone().tag(), Some(0xABCD));
+    // 禁用不影响标签
+    cell.disable();
+    assert_eq!(r.tag(), Some(0xABCD));
+}
+
+#[test]
+fn tag_survives_owner_drop_and_is_independent_of_generation() {
+    let cell = FlagCell::new(1);
+    cell.set_tag(3);
+    let r = cell.flag_borrow();
+    drop(cell);
+    assert_eq!(r.tag(), Some(3));
+    let cell = r.resurrect().unwrap();
+    assert_eq!(cell.tag(), 3);
+    assert_eq!(cell.generation(), 1);
+}
+
+#[test]
+fn empty_ref_has_no_tag() {
+    assert_eq!(FlagRef::<i32>::new().tag(), None);
+}
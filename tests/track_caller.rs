@@ -0,0 +1,58 @@
+use flag_cell::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+static LAST: Mutex<Option<(String, u32)>> = Mutex::new(None);
+
+/// 运行 `f`，返回其 panic 时报告的位置
+fn panic_location(f: impl FnOnce()) -> (String, u32) {
+    *LAST.lock().unwrap() = None;
+    let _ = panic::catch_unwind(AssertUnwindSafe(f));
+    LAST.lock().unwrap().take().expect("closure did not panic")
+}
+
+// 替换全局 panic hook，因此所有断言放在同一个测试中，避免与其他测试并发
+#[test]
+fn panics_report_the_caller_location() {
+    let prev = panic::take_hook();
+    panic::set_hook(Box::new(|info| {
+        let loc = info.location().unwrap();
+        *LAST.lock().unwrap() = Some((loc.file().to_owned(), loc.line()));
+    }));
+
+    let cell = FlagCell::new(1);
+    let guard = cell.borrow();
+    assert_eq!(panic_location(|| drop(cell.borrow_mut())), (file!().to_owned(), line!()));
+    drop(guard);
+
+    let guard = cell.borrow_mut();
+    assert_eq!(panic_location(|| drop(cell.borrow())), (file!().to_owned(), line!()));
+    drop(guard);
+
+    // 包装了借用的便捷方法同样报告调用处
+    let cell = FlagCell::new(vec![1, 2]);
+    let guard = cell.borrow();
+    assert_eq!(panic_location(|| drop(cell.replace(vec![]))), (file!().to_owned(), line!()));
+    assert_eq!(panic_location(|| drop(cell.split_off(1))), (file!().to_owned(), line!()));
+    assert_eq!(panic_location(|| drop(cell.borrow_mut_split(|v| v.split_at_mut(1)))), (file!().to_owned(), line!()));
+    assert_eq!(panic_location(|| drop(cell.try_replace_with(|v| Ok::<_, ()>(v.clone())))), (file!().to_owned(), line!()));
+    drop(guard);
+    let guard = cell.borrow_mut();
+    assert_eq!(panic_location(|| drop(cell.borrow_filter_map(|v| v.first()))), (file!().to_owned(), line!()));
+    drop(guard);
+    let copy = FlagCell::new(1);
+    let guard = copy.borrow();
+    assert_eq!(panic_location(|| copy.set(2)), (file!().to_owned(), line!()));
+    drop(guard);
+
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let unwrap = move || -> i32 { cell.unwrap() };
+    assert_eq!(panic_location(|| assert_eq!(unwrap(), 1)), (file!().to_owned(), line!() - 1));
+
+    assert_eq!(panic_location(|| drop(FlagRef::<i32>::new().try_borrow().unwrap())), (file!().to_owned(), line!()));
+    assert_eq!(panic_location(|| drop(FlagRef::<i32>::new().try_borrow().expect("x"))), (file!().to_owned(), line!()));
+    drop(r);
+
+    panic::set_hook(prev);
+}
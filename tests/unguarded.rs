@@ -0,0 +1,27 @@
+use flag_cell::*;
+
+#[test]
+fn borrow_unguarded_outlives_no_guard() {
+    let cell = FlagCell::new(vec![1, 2, 3]);
+    let r = cell.flag_borrow();
+    {
+        // SAFETY: 引用存活期间只有共享借用
+        let v = unsafe { cell.borrow_unguarded() };
+        let shared = r.try_borrow().unwrap();
+        assert_eq!(v.len(), shared.len());
+        assert_eq!(v[0], 1);
+    }
+    // 引用结束后可以正常可变借用
+    cell.borrow_mut().push(4);
+    // SAFETY: 同上
+    assert_eq!(unsafe { cell.borrow_unguarded() }.len(), 4);
+}
+
+#[test]
+#[should_panic(expected = "mutably borrowed")]
+fn borrow_unguarded_panics_while_mutably_borrowed() {
+    let cell = FlagCell::new(1);
+    let _g = cell.borrow_mut();
+    // SAFETY: 调用即 panic，不会得到引用
+    let _ = unsafe { cell.borrow_unguarded() };
+}
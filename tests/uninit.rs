@@ -0,0 +1,166 @@
+use flag_cell::*;
+use std::cell::Cell;
+use std::mem::MaybeUninit;
+
+thread_local! {
+    static DROPS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn drops() -> usize {
+    DROPS.with(Cell::get)
+}
+
+struct Counted(usize);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        DROPS.with(|d| d.set(d.get() + 1));
+    }
+}
+
+#[test]
+fn uninit_slice_fill_then_assume_init() {
+    let cell = FlagCell::<[MaybeUninit<Counted>]>::new_uninit_slice(4);
+    for (i, slot) in cell.borrow_mut().iter_mut().enumerate() {
+        slot.write(Counted(i));
+    }
+    assert_eq!(drops(), 0);
+    // SAFETY: 全部元素已写入
+    let cell = unsafe { cell.assume_init_slice() };
+    assert_eq!(cell.borrow().iter().map(|c| c.0).collect::<Vec<_>>(), [0, 1, 2, 3]);
+    assert_eq!(drops(), 0);
+    drop(cell);
+    assert_eq!(drops(), 4);
+}
+
+#[test]
+fn uninit_slice_dropped_without_init_drops_nothing() {
+    let cell = FlagCell::<[MaybeUninit<Counted>]>::new_uninit_slice(3);
+    cell.borrow_mut()[0].write(Counted(0));
+    drop(cell);
+    // 未初始化的元素不析构，已写入的元素被泄漏
+    assert_eq!(drops(), 0);
+}
+
+#[test]
+fn uninit_slice_empty_and_zst() {
+    let cell = FlagCell::<[MaybeUninit<u64>]>::new_uninit_slice(0);
+    // SAFETY: 空切片无需初始化
+    let cell = unsafe { cell.assume_init_slice() };
+    assert!(cell.borrow().is_empty());
+    let cell = FlagCell::<[MaybeUninit<()>]>::new_uninit_slice(5);
+    // SAFETY: 零大小类型无需初始化
+    let cell = unsafe { cell.assume_init_slice() };
+    assert_eq!(cell.borrow().len(), 5);
+}
+
+#[test]
+fn uninit_slice_shared_through_ref() {
+    let cell = FlagCell::<[MaybeUninit<u32>]>::new_uninit_slice(2);
+    let r = cell.flag_borrow();
+    for slot in r.try_borrow_mut().unwrap().iter_mut() {
+        slot.write(7);
+    }
+    drop(r);
+    // SAFETY: 全部元素已写入
+    let cell = unsafe { cell.assume_init_slice() };
+    assert_eq!(*cell.borrow(), [7, 7]);
+}
+
+#[test]
+#[should_panic(expected = "called `FlagCell::assume_init_slice()` while other holders exist")]
+fn uninit_slice_refuses_outstanding_ref() {
+    let cell = FlagCell::<[MaybeUninit<u32>]>::new_uninit_slice(1);
+    let r = cell.flag_borrow();
+    r.try_borrow_mut().unwrap()[0].write(1);
+    // SAFETY: 全部元素已写入
+    let _ = unsafe { cell.assume_init_slice() };
+}
+
+#[test]
+fn new_in_place_large_array() {
+    const N: usize = 1 << 16;
+    // SAFETY: 闭包写入了全部元素
+    let cell = unsafe {
+        FlagCell::<[u64; N]>::new_in_place(|slot| {
+            let base = slot.cast::<u64>();
+            for i in 0..N {
+                base.add(i).write(i as u64);
+            }
+        })
+    };
+    let arr = cell.borrow();
+    assert_eq!(arr[0], 0);
+    assert_eq!(arr[N - 1], (N - 1) as u64);
+    assert_eq!(arr.iter().sum::<u64>(), (N as u64 - 1) * N as u64 / 2);
+}
+
+#[test]
+fn new_with_constructs_in_closure() {
+    let cell = FlagCell::new_with(|| vec![0u8; 1024]);
+    assert_eq!(cell.borrow().len(), 1024);
+}
+
+#[test]
+fn new_in_place_panic_frees_without_drop() {
+    let res = std::panic::catch_unwind(|| {
+        // SAFETY: 闭包 panic，不会得到 FlagCell
+        unsafe { FlagCell::<Counted>::new_in_place(|_| panic!("init failed")) }
+    });
+    assert!(res.is_err());
+    assert_eq!(drops(), 0);
+}
+
+#[test]
+fn uninit_write_via_pointer_then_assume_init() {
+    let cell = FlagCell::<MaybeUninit<Counted>>::new_uninit();
+    let ptr = cell.as_init_ptr();
+    // SAFETY: 指针指向存活的未初始化存储，期间没有借用守卫
+    unsafe { ptr.write(Counted(7)) };
+    // SAFETY: 已经由指针写入
+    let cell = unsafe { cell.assume_init() };
+    assert_eq!(cell.borrow().0, 7);
+    assert_eq!(drops(), 0);
+    drop(cell);
+    assert_eq!(drops(), 1);
+}
+
+#[test]
+fn uninit_safe_write_then_assume_init() {
+    let cell = FlagCell::<MaybeUninit<String>>::new_uninit();
+    cell.write(String::from("staged"));
+    // SAFETY: 已经由 write 写入
+    let cell = unsafe { cell.assume_init() };
+    assert_eq!(&*cell.borrow(), "staged");
+}
+
+#[test]
+#[should_panic(expected = "called `FlagCell::assume_init()` while other holders exist")]
+fn uninit_assume_init_refuses_outstanding_ref() {
+    let cell = FlagCell::<MaybeUninit<u32>>::new_uninit();
+    let _r = cell.flag_borrow();
+    cell.write(5);
+    // SAFETY: 同上
+    let _ = unsafe { cell.assume_init() };
+}
+
+#[test]
+#[should_panic(expected = "called `FlagCell::assume_init()` while other holders exist")]
+fn uninit_assume_init_refuses_outstanding_weak() {
+    let cell = FlagCell::<MaybeUninit<u32>>::new_uninit();
+    let _w = cell.clone_ref_weak();
+    cell.write(5);
+    // SAFETY: 同上
+    let _ = unsafe { cell.assume_init() };
+}
+
+#[test]
+fn uninit_assume_init_after_refs_dropped() {
+    let cell = FlagCell::<MaybeUninit<u32>>::new_uninit();
+    let r = cell.flag_borrow();
+    r.try_borrow_mut().unwrap().write(5);
+    drop(r);
+    // SAFETY: 已经由 FlagRef 写入
+    let cell = unsafe { cell.assume_init() };
+    assert_eq!(*cell.borrow(), 5);
+}
@@ -0,0 +1,151 @@
+use flag_cell::*;
+use std::rc::Rc;
+
+#[test]
+fn failed_try_unwrap_keeps_payload() {
+    let cell = FlagCell::new(vec![String::from("a")]);
+    let r = cell.flag_borrow();
+    let cell = cell.try_unwrap().unwrap_err();
+    assert_eq!(cell.borrow()[0], "a");
+    cell.borrow_mut().push(String::from("b"));
+    assert_eq!(r.try_borrow().unwrap().len(), 2);
+    drop(r);
+    assert_eq!(cell.try_unwrap().unwrap(), ["a", "b"]);
+}
+
+#[test]
+fn failed_try_unwrap_while_disabled_then_recovers() {
+    let cell = FlagCell::new(String::from("a"));
+    let r = cell.flag_borrow();
+    cell.disable();
+    let cell = cell.try_unwrap().unwrap_err();
+    drop(r);
+    let (cell, err) = cell.try_unwrap_reason().unwrap_err();
+    assert_eq!(err, UnwrapError::Disabled);
+    cell.enable();
+    assert_eq!(cell.try_unwrap().unwrap(), "a");
+}
+
+#[test]
+fn failed_try_unwrap_drops_payload_exactly_once() {
+    let marker = Rc::new(());
+    let cell = FlagCell::new(Rc::clone(&marker));
+    let r = cell.flag_borrow();
+    let cell = cell.try_unwrap().unwrap_err();
+    assert_eq!(Rc::strong_count(&marker), 2);
+    drop(cell);
+    // FlagRef 仍持有内部数据
+    assert_eq!(Rc::strong_count(&marker), 2);
+    assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+    drop(r);
+    assert_eq!(Rc::strong_count(&marker), 1);
+}
+
+#[test]
+fn try_unwrap_reason_has_refs() {
+    let cell = FlagCell::new(1);
+    let _a = cell.flag_borrow();
+    let _b = cell.flag_borrow();
+    let (cell, err) = cell.try_unwrap_reason().unwrap_err();
+    assert_eq!(err, UnwrapError::HasRefs(2));
+    assert_eq!(*cell.borrow(), 1);
+}
+
+#[test]
+fn try_unwrap_reason_disabled() {
+    let cell = FlagCell::new(1);
+    cell.disable();
+    let (_cell, err) = cell.try_unwrap_reason().unwrap_err();
+    assert_eq!(err, UnwrapError::Disabled);
+}
+
+#[test]
+fn try_unwrap_reason_borrowed() {
+    let cell = FlagCell::new(1);
+    // 泄漏的借用守卫使借用状态永久保留
+    std::mem::forget(cell.borrow());
+    let (cell, err) = cell.try_unwrap_reason().unwrap_err();
+    assert_eq!(err, UnwrapError::Borrowed);
+    assert_eq!(*cell.borrow(), 1);
+}
+
+#[test]
+fn into_inner_unchecked_sole_owner() {
+    let marker = Rc::new(());
+    let cell = FlagCell::new(Rc::clone(&marker));
+    let weak = cell.clone_ref_weak();
+    // SAFETY: 无其他持有者与借用，数据启用
+    let value = unsafe { cell.into_inner_unchecked() };
+    assert!(Rc::ptr_eq(&value, &marker));
+    assert!(!weak.is_alive());
+    drop(weak);
+    // 值没有被重复析构
+    assert_eq!(Rc::strong_count(&marker), 2);
+    drop(value);
+    assert_eq!(Rc::strong_count(&marker), 1);
+}
+
+#[test]
+fn force_unwrap_with_outstanding_refs() {
+    let cell = FlagCell::new(String::from("taken"));
+    let w = cell.clone_ref_weak();
+    let r1 = cell.flag_borrow();
+    let r2 = r1.clone();
+    assert_eq!(cell.force_unwrap(), "taken");
+    // 核心结构仍在，所有持有者都观察到数据已取走
+    for r in [&r1, &r2] {
+        assert!(!r.is_enabled());
+        assert!(matches!(r.try_borrow(), FlagRefOption::DataDropped));
+        assert!(matches!(r.try_borrow_mut(), FlagRefOption::DataDropped));
+        assert!(matches!(r.resurrect(), FlagRefOption::DataDropped));
+    }
+    assert_eq!(r1.ref_count(), 2);
+    drop(r1);
+    assert!(r2.is_last());
+    drop(r2);
+    assert!(matches!(w.upgrade(), FlagRefOption::Empty));
+}
+
+#[test]
+fn force_unwrap_sole_owner() {
+    let cell = FlagCell::new(vec![7; 4]);
+    assert_eq!(cell.force_unwrap(), [7; 4]);
+}
+
+#[test]
+#[should_panic(expected = "called `FlagCell::force_unwrap()` on a borrowed FlagCell")]
+fn force_unwrap_panics_while_borrowed() {
+    let cell = FlagCell::new(1);
+    let r = cell.flag_borrow();
+    let _guard = r.try_borrow().unwrap();
+    cell.force_unwrap();
+}
+
+#[test]
+fn into_rc_unique_cell() {
+    let cell = FlagCell::new(vec![1, 2]);
+    let rc = cell.into_rc().ok().unwrap();
+    assert_eq!(*rc, [1, 2]);
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn into_rc_with_refs_returns_cell() {
+    let cell = FlagCell::new(String::from("shared"));
+    let r = cell.flag_borrow();
+    let Err(cell) = cell.into_rc() else {
+        panic!("into_rc should fail while refs exist");
+    };
+    assert_eq!(&*r.try_borrow().unwrap(), "shared");
+    drop(r);
+    assert_eq!(&*cell.into_rc().ok().unwrap(), "shared");
+}
+
+#[test]
+fn into_rc_detaches_weak_refs() {
+    let cell = FlagCell::new(5);
+    let w = cell.clone_ref_weak();
+    let rc = cell.into_rc().ok().unwrap();
+    assert_eq!(*rc, 5);
+    assert!(matches!(w.upgrade(), FlagRefOption::Empty));
+}
@@ -0,0 +1,99 @@
+use flag_cell::*;
+
+#[test]
+fn split_off_at_various_indices() {
+    let cell = FlagCell::new(vec![1, 2, 3, 4]);
+    assert_eq!(cell.split_off(2), [3, 4]);
+    assert_eq!(*cell.borrow(), [1, 2]);
+    let len = cell.borrow().len();
+    assert_eq!(cell.split_off(len), Vec::<i32>::new());
+    assert_eq!(*cell.borrow(), [1, 2]);
+    assert_eq!(cell.split_off(0), [1, 2]);
+    assert!(cell.borrow().is_empty());
+}
+
+#[test]
+#[should_panic]
+fn split_off_out_of_bounds() {
+    let cell = FlagCell::new(vec![1, 2]);
+    let _ = cell.split_off(3);
+}
+
+#[test]
+#[should_panic]
+fn split_off_while_borrowed() {
+    let cell = FlagCell::new(vec![1, 2]);
+    let _g = cell.borrow();
+    let _ = cell.split_off(1);
+}
+
+fn sum(xs: &[i32]) -> i32 {
+    xs.iter().sum()
+}
+
+fn double(xs: &mut [i32]) {
+    xs.iter_mut().for_each(|x| *x *= 2);
+}
+
+#[test]
+fn slice_guards_pass_to_slice_apis() {
+    let cell = FlagCell::new(vec![1, 2, 3]);
+    assert_eq!(sum(&cell.borrow_slice()), 6);
+    double(&mut cell.borrow_mut_slice());
+    assert_eq!(sum(&cell.borrow_slice()), 12);
+}
+
+#[test]
+#[should_panic]
+fn borrow_mut_slice_panics_while_borrowed() {
+    let cell = FlagCell::new(vec![1]);
+    let _g = cell.borrow_slice();
+    let _ = cell.borrow_mut_slice();
+}
+
+#[test]
+fn drain_into_moves_all_items() {
+    let queue = FlagCell::new(vec![1, 2, 3]);
+    let mut buffer = vec![0];
+    queue.drain_into(&mut buffer);
+    assert_eq!(buffer, [0, 1, 2, 3]);
+    assert!(queue.borrow().is_empty());
+    queue.borrow_mut().push(4);
+    queue.drain_into(&mut buffer);
+    assert_eq!(buffer, [0, 1, 2, 3, 4]);
+}
+
+#[test]
+#[should_panic]
+fn drain_into_panics_while_borrowed() {
+    let queue = FlagCell::new(vec![1]);
+    let _g = queue.borrow();
+    queue.drain_into(&mut Vec::new());
+}
+
+#[test]
+fn shrink_inner_vec() {
+    let cell = FlagCell::<Vec<u8>>::with_capacity(64);
+    cell.borrow_mut().push(1);
+    assert!(cell.borrow().capacity() >= 64);
+    cell.shrink_inner();
+    assert!(cell.borrow().capacity() < 64);
+    assert_eq!(*cell.borrow(), [1]);
+}
+
+#[test]
+fn shrink_inner_string() {
+    let cell = FlagCell::<String>::with_capacity(64);
+    cell.borrow_mut().push('a');
+    cell.shrink_inner();
+    assert!(cell.borrow().capacity() < 64);
+    assert_eq!(&*cell.borrow(), "a");
+}
+
+#[test]
+#[should_panic]
+fn shrink_inner_panics_while_borrowed() {
+    let cell = FlagCell::new(vec![1]);
+    let _g = cell.borrow();
+    cell.shrink_inner();
+}
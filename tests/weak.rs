@@ -0,0 +1,45 @@
+use flag_cell::*;
+use std::rc::Rc;
+
+#[test]
+fn weak_does_not_keep_payload_alive() {
+    let marker = Rc::new(());
+    let cell = FlagCell::new(Rc::clone(&marker));
+    let weak = cell.clone_ref_weak();
+    assert!(weak.is_alive());
+    assert_eq!(Rc::strong_count(&marker), 2);
+    drop(cell);
+    assert_eq!(Rc::strong_count(&marker), 1);
+    assert!(!weak.is_alive());
+    assert!(matches!(weak.upgrade(), FlagRefOption::Empty));
+}
+
+#[test]
+fn weak_upgrades_while_alive() {
+    let cell = FlagCell::new(1);
+    let weak = cell.clone_ref_weak();
+    let r = weak.upgrade().unwrap();
+    assert_eq!(cell.ref_count(), 1);
+    drop(cell);
+    // FlagRef 仍保活数据，弱引用仍可升级
+    assert!(weak.is_alive());
+    assert!(matches!(weak.upgrade(), FlagRefOption::Some(_)));
+    drop(r);
+    assert!(matches!(weak.upgrade(), FlagRefOption::Empty));
+}
+
+#[test]
+fn weak_upgrades_after_payload_dropped() {
+    let mut cell = FlagCell::new(String::from("a"));
+    let weak = cell.clone_ref_weak();
+    assert!(cell.drop_payload());
+    assert!(weak.is_alive());
+    let FlagRefOption::Some(r) = weak.upgrade() else {
+        panic!("upgrade failed");
+    };
+    assert!(matches!(r.try_borrow(), FlagRefOption::DataDropped));
+    drop(r);
+    drop(cell);
+    assert!(!weak.is_alive());
+    assert!(matches!(weak.upgrade(), FlagRefOption::Empty));
+}
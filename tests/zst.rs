@@ -0,0 +1,82 @@
+use flag_cell::*;
+
+use std::cell::Cell;
+
+#[test]
+fn zst_flag_lifecycle() {
+    let cell = FlagCell::new(());
+    let r = cell.flag_borrow();
+    assert!(matches!(r.try_borrow(), FlagRefOption::Some(_)));
+    
+    cell.disable();
+    assert!(!r.is_enabled());
+    assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+    cell.enable();
+    assert!(r.is_enabled());
+    
+    drop(cell);
+    assert!(matches!(r.try_borrow(), FlagRefOption::Disabled));
+    let FlagRefOption::Some(owner) = r.resurrect() else {
+        panic!("resurrect failed");
+    };
+    assert!(r.is_enabled());
+    assert_eq!(owner.ref_count(), 1);
+    drop(owner);
+    assert!(r.is_last());
+    drop(r);
+}
+
+#[test]
+fn zst_weak_outlives_payload() {
+    let cell = FlagCell::new(());
+    let w = cell.clone_ref_weak();
+    drop(cell);
+    assert!(matches!(w.upgrade(), FlagRefOption::Empty));
+}
+
+#[test]
+fn many_zst_cells_with_outstanding_refs() {
+    let mut refs = Vec::new();
+    let mut owners = Vec::new();
+    for i in 0..256 {
+        let cell = FlagCell::new(());
+        refs.push(cell.flag_borrow());
+        refs.push(cell.flag_borrow());
+        if i % 3 == 0 {
+            drop(cell);
+        } else {
+            owners.push(cell);
+        }
+    }
+    assert_eq!(refs.iter().filter(|r| r.is_enabled()).count(), owners.len() * 2);
+    // 一部分由持有者先行销毁，其余由 FlagRef 最后释放
+    owners.truncate(owners.len() / 2);
+    refs.retain(|r| r.is_enabled());
+    drop(owners);
+    assert!(refs.iter().all(|r| !r.is_enabled()));
+}
+
+thread_local! {
+    static DROPS: Cell<usize> = const { Cell::new(0) };
+}
+
+struct Noisy;
+
+impl Drop for Noisy {
+    fn drop(&mut self) {
+        DROPS.with(|d| d.set(d.get() + 1));
+    }
+}
+
+#[test]
+fn zst_payload_dropped_exactly_once() {
+    assert_eq!(std::mem::size_of::<Noisy>(), 0);
+    let cell = FlagCell::new(Noisy);
+    let r1 = cell.flag_borrow();
+    let r2 = r1.clone();
+    drop(cell);
+    assert_eq!(DROPS.with(Cell::get), 0);
+    drop(r1);
+    drop(r2);
+    assert_eq!(DROPS.with(Cell::get), 1);
+}